@@ -0,0 +1,204 @@
+use crate::{
+    Anchor, CasAuth, Config, DidResolvers, HttpApi, Indexing, IndexingDb, Ipfs, Logger, Metrics,
+    NetworkDefinition, NetworkIdentifier, NetworkRegistry, Node, StateStore,
+};
+
+/// Builder for a [`Config`]. Network defaults are applied first when building, so sections set
+/// on the builder are never overwritten, regardless of the order they are set in.
+#[derive(Clone, Debug)]
+pub struct ConfigBuilder {
+    network: NetworkDefinition,
+    name: String,
+    cas_auth: Option<CasAuth>,
+    ipfs: Option<Ipfs>,
+    state_store: Option<StateStore>,
+    http_api: Option<HttpApi>,
+    anchor: Option<Anchor>,
+    ethereum_rpc_url: Option<String>,
+    indexing: Option<Indexing>,
+    indexing_db: Option<IndexingDb>,
+    did_resolvers: Option<DidResolvers>,
+    node: Option<Node>,
+    logger: Option<Logger>,
+    metrics: Option<Metrics>,
+}
+
+impl ConfigBuilder {
+    pub fn new(network: &NetworkDefinition, name: &str) -> Self {
+        Self {
+            network: network.clone(),
+            name: name.to_string(),
+            cas_auth: None,
+            ipfs: None,
+            state_store: None,
+            http_api: None,
+            anchor: None,
+            ethereum_rpc_url: None,
+            indexing: None,
+            indexing_db: None,
+            did_resolvers: None,
+            node: None,
+            logger: None,
+            metrics: None,
+        }
+    }
+
+    /// Anchor with the given CAS authorization, unless an anchor is set with [`Self::anchor`]
+    pub fn cas_auth(mut self, cas_auth: CasAuth) -> Self {
+        self.cas_auth = Some(cas_auth);
+        self
+    }
+
+    pub fn ipfs(mut self, ipfs: Ipfs) -> Self {
+        self.ipfs = Some(ipfs);
+        self
+    }
+
+    pub fn state_store(mut self, state_store: StateStore) -> Self {
+        self.state_store = Some(state_store);
+        self
+    }
+
+    pub fn http_api(mut self, http_api: HttpApi) -> Self {
+        self.http_api = Some(http_api);
+        self
+    }
+
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    pub fn ethereum_rpc_url(mut self, url: impl Into<String>) -> Self {
+        self.ethereum_rpc_url = Some(url.into());
+        self
+    }
+
+    pub fn indexing(mut self, indexing: Indexing) -> Self {
+        self.indexing = Some(indexing);
+        self
+    }
+
+    /// Set only the indexing database, keeping the network default for the other settings
+    pub fn indexing_db(mut self, db: IndexingDb) -> Self {
+        self.indexing_db = Some(db);
+        self
+    }
+
+    pub fn did_resolvers(mut self, did_resolvers: DidResolvers) -> Self {
+        self.did_resolvers = Some(did_resolvers);
+        self
+    }
+
+    pub fn node(mut self, node: Node) -> Self {
+        self.node = Some(node);
+        self
+    }
+
+    pub fn logger(mut self, logger: Logger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Build the config without validating it
+    pub fn build_unchecked(self) -> Config {
+        let mut cfg = Config::for_network(&self.network, &self.name, self.cas_auth);
+        if let Some(ipfs) = self.ipfs {
+            cfg.ipfs = ipfs;
+        }
+        if let Some(state_store) = self.state_store {
+            cfg.state_store = state_store;
+        }
+        if let Some(http_api) = self.http_api {
+            cfg.http_api = http_api;
+        }
+        if let Some(anchor) = self.anchor {
+            cfg.anchor = anchor;
+        }
+        if self.ethereum_rpc_url.is_some() {
+            cfg.ethereum_rpc_url = self.ethereum_rpc_url;
+        }
+        if let Some(indexing) = self.indexing {
+            cfg.indexing = indexing;
+        }
+        if let Some(db) = self.indexing_db {
+            cfg.indexing.db = db;
+        }
+        if let Some(did_resolvers) = self.did_resolvers {
+            cfg.did_resolvers = did_resolvers;
+        }
+        if let Some(node) = self.node {
+            cfg.node = node;
+        }
+        if let Some(logger) = self.logger {
+            cfg.logger = logger;
+        }
+        if let Some(metrics) = self.metrics {
+            cfg.metrics = metrics;
+        }
+        cfg
+    }
+
+    /// Build and validate the config. Warnings are logged, errors fail the build.
+    pub fn build(self) -> anyhow::Result<Config> {
+        let cfg = self.build_unchecked();
+        let mut errors = vec![];
+        for d in cfg.validate() {
+            if d.is_error() {
+                errors.push(d.to_string());
+            } else {
+                log::warn!("{}", d);
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid config: {}", errors.join("; "));
+        }
+        Ok(cfg)
+    }
+}
+
+impl Config {
+    pub fn builder(id: &NetworkIdentifier, name: &str) -> ConfigBuilder {
+        ConfigBuilder::new(&NetworkRegistry::for_identifier(id), name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_sections_set_before_network_defaults() {
+        let cfg = Config::builder(&NetworkIdentifier::Mainnet, "test")
+            .anchor(Anchor::SelfHosted {
+                url: "https://cas.internal".to_string(),
+                auth_method: None,
+                private_seed_url: None,
+            })
+            .cas_auth(CasAuth {
+                url: "https://cas.3boxlabs.com".to_string(),
+                pk: None,
+            })
+            .indexing_db(IndexingDb::default())
+            .metrics(Metrics::Enabled("http://localhost:4318".to_string()))
+            .build()
+            .unwrap();
+        assert_eq!(cfg.anchor.url(), Some("https://cas.internal"));
+        assert_eq!(cfg.network.id, NetworkIdentifier::Mainnet);
+        assert!(cfg.indexing.enable_historical_sync);
+        assert!(matches!(cfg.metrics, Metrics::Enabled(_)));
+    }
+
+    #[test]
+    fn should_fail_invalid_config() {
+        let res = Config::builder(&NetworkIdentifier::Mainnet, "test")
+            .indexing_db(IndexingDb::Sqlite("/tmp/ceramic.db".into()))
+            .build();
+        assert!(res.is_err());
+    }
+}
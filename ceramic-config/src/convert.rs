@@ -1,4 +1,15 @@
-use crate::{Anchor, LogLevel, Metrics, NetworkIdentifier};
+use crate::{
+    Anchor, Config, DidResolvers, FileLogger, Indexing, IndexingDb, Ipfs, IpfsRemote, LogLevel,
+    Logger, Metrics, Network, NetworkIdentifier, Node, S3StateStore, Secret, StateStore,
+};
+use std::path::PathBuf;
+
+/// Daemon sync override used when `Node::sync_override` is set
+const SYNC_ALWAYS: &str = "sync-always";
+/// Daemon default sync behavior, used when `Node::sync_override` is not set
+const PREFER_CACHE: &str = "prefer-cache";
+/// Daemon anchor auth method for DID authentication
+const DID_AUTH_METHOD: &str = "did";
 
 fn convert_log_level(level: LogLevel) -> u16 {
     match level {
@@ -10,6 +21,17 @@ fn convert_log_level(level: LogLevel) -> u16 {
     }
 }
 
+fn convert_daemon_log_level(level: u16) -> Option<LogLevel> {
+    match level {
+        0 => Some(LogLevel::Error),
+        1 => Some(LogLevel::Warn),
+        2 => Some(LogLevel::Info),
+        3 => Some(LogLevel::Debug),
+        4 => Some(LogLevel::Trace),
+        _ => None,
+    }
+}
+
 pub fn convert_network_identifier(id: &NetworkIdentifier) -> &'static str {
     match id {
         NetworkIdentifier::InMemory => "inmemory",
@@ -20,21 +42,40 @@ pub fn convert_network_identifier(id: &NetworkIdentifier) -> &'static str {
     }
 }
 
-impl Into<crate::daemon::DaemonConfig> for crate::Config {
-    fn into(self) -> crate::daemon::DaemonConfig {
+pub fn convert_daemon_network_name(name: &str) -> Option<NetworkIdentifier> {
+    match name {
+        "inmemory" => Some(NetworkIdentifier::InMemory),
+        "local" => Some(NetworkIdentifier::Local),
+        "dev-unstable" => Some(NetworkIdentifier::Dev),
+        "testnet-clay" => Some(NetworkIdentifier::Clay),
+        "mainnet" => Some(NetworkIdentifier::Mainnet),
+        _ => None,
+    }
+}
+
+/// Secrets are resolved, so the daemon config holds their values
+impl TryFrom<Config> for crate::daemon::DaemonConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(cfg: Config) -> anyhow::Result<Self> {
         let mut node = crate::daemon::DaemonNodeConfig {
-            sync_override: None,
-            gateway: Some(self.node.gateway),
-            stream_cache_limit: None,
+            sync_override: if cfg.node.sync_override {
+                Some(SYNC_ALWAYS.to_string())
+            } else {
+                None
+            },
+            gateway: Some(cfg.node.gateway),
+            stream_cache_limit: Some(cfg.node.stream_cache_limit),
             private_seed_url: None,
         };
-        let anchor = match self.anchor {
+        let ethereum_rpc_url = cfg.ethereum_rpc_url;
+        let anchor = match cfg.anchor {
             Anchor::None => {
                 //TODO: This is a hack to get around the fact that the anchor service must be present
                 Some(crate::daemon::DaemonAnchorConfig {
                     anchor_service_url: None,
                     auth_method: None,
-                    ethereum_rpc_url: None,
+                    ethereum_rpc_url,
                 })
             }
             Anchor::Ip { url } => {
@@ -42,7 +83,7 @@ impl Into<crate::daemon::DaemonConfig> for crate::Config {
                 Some(crate::daemon::DaemonAnchorConfig {
                     anchor_service_url: Some(url),
                     auth_method: None,
-                    ethereum_rpc_url: None,
+                    ethereum_rpc_url,
                 })
             }
             Anchor::RemoteDid {
@@ -50,52 +91,77 @@ impl Into<crate::daemon::DaemonConfig> for crate::Config {
                 private_seed_url,
             } => {
                 log::info!("Anchor using {} with DID Authentication. Please see https://composedb.js.org/docs/0.4.x/guides/composedb-server/access-mainnet#updating-to-did-based-authentication for more information", url);
-                node.private_seed_url = Some(private_seed_url);
+                node.private_seed_url = Some(private_seed_url.resolve()?);
+                Some(crate::daemon::DaemonAnchorConfig {
+                    anchor_service_url: Some(url),
+                    auth_method: Some(DID_AUTH_METHOD.to_string()),
+                    ethereum_rpc_url,
+                })
+            }
+            Anchor::SelfHosted {
+                url,
+                auth_method,
+                private_seed_url,
+            } => {
+                log::info!("Anchor using self-hosted CAS {}", url);
+                node.private_seed_url = private_seed_url.map(|s| s.resolve()).transpose()?;
                 Some(crate::daemon::DaemonAnchorConfig {
                     anchor_service_url: Some(url),
-                    auth_method: Some("did".to_string()),
-                    ethereum_rpc_url: None,
+                    auth_method,
+                    ethereum_rpc_url,
                 })
             }
         };
-        let cors = if self.http_api.cors_allowed_origins.is_empty() {
+        let cors = if cfg.http_api.cors_allowed_origins.is_empty() {
             None
         } else {
-            Some(self.http_api.cors_allowed_origins)
+            Some(cfg.http_api.cors_allowed_origins)
         };
         let http = Some(crate::daemon::DaemonHttpApiConfig {
-            hostname: Some(self.http_api.hostname),
-            port: Some(self.http_api.port),
-            admin_dids: Some(self.http_api.admin_dids),
+            hostname: Some(cfg.http_api.hostname),
+            port: Some(cfg.http_api.port),
+            admin_dids: Some(cfg.http_api.admin_dids),
             cors_allowed_origins: cors,
         });
-        let ipfs = Some(if let crate::Ipfs::Remote(r) = self.ipfs {
+        let ipfs = Some(if let crate::Ipfs::Remote(r) = cfg.ipfs {
             crate::daemon::DaemonIpfsConfig {
                 mode: Some(crate::daemon::DaemonIpfsConfigMode::Remote),
                 host: Some(r.host),
-                pinning_endpoints: None,
+                pinning_endpoints: if r.pinning_endpoints.is_empty() {
+                    None
+                } else {
+                    Some(r.pinning_endpoints)
+                },
+                disable_peer_data_sync: if r.disable_peer_data_sync {
+                    Some(true)
+                } else {
+                    None
+                },
+                request_timeout: r.request_timeout_ms,
             }
         } else {
             crate::daemon::DaemonIpfsConfig {
                 mode: Some(crate::daemon::DaemonIpfsConfigMode::Bundled),
                 host: None,
                 pinning_endpoints: None,
+                disable_peer_data_sync: None,
+                request_timeout: None,
             }
         });
-        let logger = Some(if let Some(l) = self.logger.file {
+        let logger = Some(if let Some(l) = cfg.logger.file {
             crate::daemon::DaemonLoggerConfig {
                 log_to_files: Some(l.enabled),
                 log_directory: Some(l.directory.to_string_lossy().to_string()),
-                log_level: Some(convert_log_level(self.logger.level)),
+                log_level: Some(convert_log_level(cfg.logger.level)),
             }
         } else {
             crate::daemon::DaemonLoggerConfig {
                 log_to_files: Some(false),
                 log_directory: None,
-                log_level: Some(convert_log_level(self.logger.level)),
+                log_level: Some(convert_log_level(cfg.logger.level)),
             }
         });
-        let metrics = if let Metrics::Enabled(url) = self.metrics {
+        let metrics = if let Metrics::Enabled(url) = cfg.metrics {
             crate::daemon::DaemonMetricsConfig {
                 metrics_exporter_enabled: true,
                 collector_host: Some(url),
@@ -107,10 +173,10 @@ impl Into<crate::daemon::DaemonConfig> for crate::Config {
             }
         };
         let network = Some(crate::daemon::DaemonNetworkConfig {
-            name: Some(convert_network_identifier(&self.network.id).to_string()),
-            pubsub_topic: self.network.pubsub_topic,
+            name: Some(convert_network_identifier(&cfg.network.id).to_string()),
+            pubsub_topic: cfg.network.pubsub_topic,
         });
-        let state_store = Some(match self.state_store {
+        let state_store = Some(match cfg.state_store {
             crate::StateStore::S3(s3) => crate::daemon::DaemonStateStoreConfig {
                 s3_bucket: Some(s3.bucket),
                 s3_endpoint: Some(s3.endpoint),
@@ -125,13 +191,21 @@ impl Into<crate::daemon::DaemonConfig> for crate::Config {
             },
         });
         let indexing = Some(crate::daemon::DaemonIndexingConfig {
-            db: Some(self.indexing.db),
+            db: Some(cfg.indexing.db.url()?),
             allow_queries_before_historical_sync: Some(
-                self.indexing.allow_queries_before_historical_sync,
+                cfg.indexing.allow_queries_before_historical_sync,
             ),
-            enable_historical_sync: Some(self.indexing.enable_historical_sync),
+            enable_historical_sync: Some(cfg.indexing.enable_historical_sync),
         });
-        crate::daemon::DaemonConfig {
+        let DidResolvers::Ethr(ethr) = cfg.did_resolvers;
+        let did_resolvers = if ethr.is_empty() {
+            None
+        } else {
+            Some(crate::daemon::DaemonResolversConfig {
+                ethr_did_resolver: Some(ethr),
+            })
+        };
+        Ok(crate::daemon::DaemonConfig {
             anchor: anchor,
             http_api: http,
             ipfs: ipfs,
@@ -141,7 +215,430 @@ impl Into<crate::daemon::DaemonConfig> for crate::Config {
             node: Some(node),
             state_store: state_store,
             indexing: indexing,
-            did_resolvers: None,
+            did_resolvers,
+        })
+    }
+}
+
+/// Result of importing a daemon config, along with any settings that could not be represented
+pub struct DaemonConfigImport {
+    pub config: Config,
+    pub dropped: Vec<String>,
+}
+
+impl DaemonConfigImport {
+    fn drop_setting(&mut self, setting: &str, reason: impl std::fmt::Display) {
+        self.dropped.push(format!("{}: {}", setting, reason));
+    }
+}
+
+/// Convert an existing ceramic daemon config into a wheel config, reporting any settings that
+/// could not be carried over
+pub fn import_daemon_config(
+    daemon: crate::daemon::DaemonConfig,
+) -> anyhow::Result<DaemonConfigImport> {
+    let mut import = DaemonConfigImport {
+        config: Config::default(),
+        dropped: vec![],
+    };
+
+    let network = daemon
+        .network
+        .unwrap_or(crate::daemon::DaemonNetworkConfig {
+            name: None,
+            pubsub_topic: None,
+        });
+    let id = if let Some(name) = network.name {
+        convert_daemon_network_name(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown network name {}", name))?
+    } else {
+        anyhow::bail!("Daemon config does not specify network.name");
+    };
+    import.config.network = Network {
+        id,
+        pubsub_topic: network.pubsub_topic,
+        ..Default::default()
+    };
+
+    let private_seed_url = daemon
+        .node
+        .as_ref()
+        .and_then(|n| n.private_seed_url.clone());
+    let (anchor_service_url, auth_method) = match daemon.anchor {
+        None => (None, None),
+        Some(anchor) => {
+            import.config.ethereum_rpc_url = anchor.ethereum_rpc_url;
+            (anchor.anchor_service_url, anchor.auth_method)
         }
+    };
+    let network_cas = Anchor::url_for_network(&import.config.network.id);
+    import.config.anchor = match anchor_service_url {
+        None => {
+            if let Some(auth_method) = auth_method {
+                import.drop_setting(
+                    "anchor.auth-method",
+                    format!("{} unused without anchor.anchor-service-url", auth_method),
+                );
+            }
+            if private_seed_url.is_some() {
+                import.drop_setting(
+                    "node.private-seed-url",
+                    "only used for DID anchor authentication",
+                );
+            }
+            Anchor::None
+        }
+        Some(url)
+            if network_cas.as_deref().map(|u| u.trim_end_matches('/'))
+                != Some(url.trim_end_matches('/')) =>
+        {
+            Anchor::SelfHosted {
+                url,
+                auth_method,
+                private_seed_url: private_seed_url.map(Secret::new),
+            }
+        }
+        Some(url) => match auth_method.as_deref() {
+            None => {
+                if private_seed_url.is_some() {
+                    import.drop_setting(
+                        "node.private-seed-url",
+                        "only used for DID anchor authentication",
+                    );
+                }
+                Anchor::Ip { url }
+            }
+            Some(DID_AUTH_METHOD) => {
+                let private_seed_url = private_seed_url.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "anchor.auth-method is did, but node.private-seed-url is not set"
+                    )
+                })?;
+                Anchor::RemoteDid {
+                    url,
+                    private_seed_url: Secret::new(private_seed_url),
+                }
+            }
+            Some(_) => Anchor::SelfHosted {
+                url,
+                auth_method,
+                private_seed_url: private_seed_url.map(Secret::new),
+            },
+        },
+    };
+
+    if let Some(http) = daemon.http_api {
+        if let Some(hostname) = http.hostname {
+            import.config.http_api.hostname = hostname;
+        }
+        if let Some(port) = http.port {
+            import.config.http_api.port = port;
+        }
+        if let Some(cors) = http.cors_allowed_origins {
+            import.config.http_api.cors_allowed_origins = cors;
+        }
+        if let Some(admin_dids) = http.admin_dids {
+            import.config.http_api.admin_dids = admin_dids;
+        }
+    }
+
+    import.config.ipfs = match daemon.ipfs {
+        None => Ipfs::Bundled,
+        Some(ipfs) => match (ipfs.mode, ipfs.host) {
+            (Some(crate::daemon::DaemonIpfsConfigMode::Remote), host) | (None, host @ Some(_)) => {
+                Ipfs::Remote(IpfsRemote {
+                    host: host.unwrap_or_else(|| IpfsRemote::default().host),
+                    pinning_endpoints: ipfs.pinning_endpoints.unwrap_or_default(),
+                    disable_peer_data_sync: ipfs.disable_peer_data_sync.unwrap_or(false),
+                    request_timeout_ms: ipfs.request_timeout,
+                })
+            }
+            (_, host) => {
+                for (setting, value) in [
+                    ("ipfs.host", host),
+                    (
+                        "ipfs.pinning-endpoints",
+                        ipfs.pinning_endpoints.map(|e| e.join(",")),
+                    ),
+                    (
+                        "ipfs.disable-peer-data-sync",
+                        ipfs.disable_peer_data_sync.map(|d| d.to_string()),
+                    ),
+                    (
+                        "ipfs.request-timeout",
+                        ipfs.request_timeout.map(|t| t.to_string()),
+                    ),
+                ] {
+                    if let Some(value) = value {
+                        import.drop_setting(setting, format!("{} unused with bundled ipfs", value));
+                    }
+                }
+                Ipfs::Bundled
+            }
+        },
+    };
+
+    if let Some(state_store) = daemon.state_store {
+        import.config.state_store = match state_store.mode {
+            Some(crate::daemon::DaemonStateStoreMode::S3) => {
+                let bucket = state_store
+                    .s3_bucket
+                    .ok_or_else(|| anyhow::anyhow!("state-store.s3-bucket is required for s3"))?;
+                if let Some(l) = state_store.local_directory {
+                    import.drop_setting(
+                        "state-store.local-directory",
+                        format!("{} unused with s3", l),
+                    );
+                }
+                StateStore::S3(S3StateStore {
+                    bucket,
+                    endpoint: state_store.s3_endpoint.unwrap_or_default(),
+                })
+            }
+            Some(crate::daemon::DaemonStateStoreMode::File) | None => {
+                if let Some(b) = state_store.s3_bucket {
+                    import.drop_setting("state-store.s3-bucket", format!("{} unused with file", b));
+                }
+                if let Some(e) = state_store.s3_endpoint {
+                    import
+                        .drop_setting("state-store.s3-endpoint", format!("{} unused with file", e));
+                }
+                state_store
+                    .local_directory
+                    .map(|l| StateStore::LocalDirectory(PathBuf::from(l)))
+                    .unwrap_or_default()
+            }
+        };
+    }
+
+    if let Some(logger) = daemon.logger {
+        let level = if let Some(l) = logger.log_level {
+            convert_daemon_log_level(l).unwrap_or_else(|| {
+                import.drop_setting("logger.log-level", format!("{} is not a known level", l));
+                LogLevel::default()
+            })
+        } else {
+            LogLevel::default()
+        };
+        let file = match (logger.log_to_files, logger.log_directory) {
+            (Some(false), None) => None,
+            (enabled, directory) => Some(FileLogger {
+                enabled: enabled.unwrap_or(false),
+                directory: directory
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| FileLogger::default().directory),
+            }),
+        };
+        import.config.logger = Logger { file, level };
+    }
+
+    if let Some(metrics) = daemon.metrics {
+        import.config.metrics = match (metrics.metrics_exporter_enabled, metrics.collector_host) {
+            (true, Some(host)) => Metrics::Enabled(host),
+            (true, None) => {
+                import.drop_setting(
+                    "metrics.metrics-exporter-enabled",
+                    "no collector-host specified",
+                );
+                Metrics::Disabled
+            }
+            (false, host) => {
+                if let Some(host) = host {
+                    import.drop_setting(
+                        "metrics.collector-host",
+                        format!("{} unused when disabled", host),
+                    );
+                }
+                Metrics::Disabled
+            }
+        };
+    }
+
+    if let Some(node) = daemon.node {
+        let defaults = Node::default();
+        let sync_override = match node.sync_override.as_deref() {
+            None => defaults.sync_override,
+            Some(SYNC_ALWAYS) => true,
+            Some(PREFER_CACHE) => false,
+            Some(other) => {
+                import.drop_setting("node.sync-override", format!("{} is not supported", other));
+                defaults.sync_override
+            }
+        };
+        import.config.node = Node {
+            gateway: node.gateway.unwrap_or(defaults.gateway),
+            sync_override,
+            stream_cache_limit: node
+                .stream_cache_limit
+                .unwrap_or(defaults.stream_cache_limit),
+        };
+    }
+
+    if let Some(indexing) = daemon.indexing {
+        let defaults = Indexing::default();
+        import.config.indexing = Indexing {
+            db: indexing
+                .db
+                .map(|db| IndexingDb::from_url(&db))
+                .transpose()?
+                .unwrap_or(defaults.db),
+            allow_queries_before_historical_sync: indexing
+                .allow_queries_before_historical_sync
+                .unwrap_or(defaults.allow_queries_before_historical_sync),
+            enable_historical_sync: indexing
+                .enable_historical_sync
+                .unwrap_or(defaults.enable_historical_sync),
+        };
+    }
+
+    if let Some(resolvers) = daemon.did_resolvers.and_then(|r| r.ethr_did_resolver) {
+        import.config.did_resolvers = DidResolvers::Ethr(resolvers);
+    }
+
+    Ok(import)
+}
+
+impl TryFrom<crate::daemon::DaemonConfig> for Config {
+    type Error = anyhow::Error;
+
+    fn try_from(daemon: crate::daemon::DaemonConfig) -> Result<Self, Self::Error> {
+        let import = import_daemon_config(daemon)?;
+        for dropped in import.dropped {
+            log::warn!("Daemon config setting not imported, {}", dropped);
+        }
+        Ok(import.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_import_daemon_config() {
+        let daemon: crate::daemon::DaemonConfig = serde_json::from_str(
+            r#"{
+                "anchor": {
+                    "anchor-service-url": "https://cas-clay.3boxlabs.com",
+                    "auth-method": "did"
+                },
+                "http-api": { "hostname": "0.0.0.0", "port": 7007, "admin-dids": ["did:key:z6Mk"] },
+                "ipfs": { "mode": "remote", "host": "http://ipfs:5001", "pinning-endpoints": ["ipfs+context"] },
+                "logger": { "log-level": 3, "log-to-files": false },
+                "network": { "name": "testnet-clay" },
+                "node": { "private-seed-url": "inplace:ed25519#00" },
+                "state-store": { "mode": "s3", "s3-bucket": "ceramic", "s3-endpoint": "https://s3" },
+                "indexing": { "db": "postgres://ceramic@localhost/ceramic" }
+            }"#,
+        )
+        .unwrap();
+        let import = import_daemon_config(daemon).unwrap();
+        let cfg = import.config;
+        assert_eq!(cfg.network.id, NetworkIdentifier::Clay);
+        assert!(
+            matches!(cfg.anchor, Anchor::RemoteDid { ref private_seed_url, .. } if private_seed_url.reference() == "inplace:ed25519#00")
+        );
+        assert!(
+            matches!(cfg.ipfs, Ipfs::Remote(ref r) if r.host == "http://ipfs:5001" && r.pinning_endpoints == vec!["ipfs+context"])
+        );
+        assert!(matches!(cfg.state_store, StateStore::S3(ref s3) if s3.bucket == "ceramic"));
+        assert!(matches!(cfg.logger.level, LogLevel::Debug));
+        assert!(cfg.logger.file.is_none());
+        assert!(import.dropped.is_empty());
+    }
+
+    #[test]
+    fn should_roundtrip_through_daemon_config() {
+        let mut cfg = Config::new(
+            &NetworkIdentifier::Mainnet,
+            "test",
+            Some(crate::CasAuth {
+                url: "https://cas.3boxlabs.com".to_string(),
+                pk: Some("inplace:ed25519#00".to_string()),
+            }),
+        );
+        cfg.node.sync_override = true;
+        cfg.node.stream_cache_limit = 500;
+        cfg.did_resolvers
+            .set_ethr_networks(vec![crate::EthrNetwork {
+                name: "mainnet".to_string(),
+                rpc_url: "https://mainnet.infura.io/v3/key".to_string(),
+            }]);
+        cfg.ipfs = Ipfs::Remote(IpfsRemote {
+            host: "http://ipfs:5001".to_string(),
+            pinning_endpoints: vec!["ipfs+context".to_string()],
+            disable_peer_data_sync: true,
+            request_timeout_ms: Some(30000),
+        });
+        let daemon = crate::daemon::DaemonConfig::try_from(cfg.clone()).unwrap();
+        let js = serde_json::to_string(&daemon).unwrap();
+        assert!(js.contains(r#""sync-override":"sync-always""#));
+        assert!(js.contains(r#""ethr-did-resolver":{"networks""#));
+        let daemon: crate::daemon::DaemonConfig = serde_json::from_str(&js).unwrap();
+        let import = import_daemon_config(daemon).unwrap();
+        assert!(import.dropped.is_empty());
+        assert_eq!(
+            serde_json::to_value(&cfg).unwrap(),
+            serde_json::to_value(&import.config).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_roundtrip_self_hosted_anchor() {
+        let mut cfg = Config::new(&NetworkIdentifier::Local, "test", None);
+        let daemon = crate::daemon::DaemonConfig::try_from(cfg.clone()).unwrap();
+        let anchor = daemon.anchor.unwrap();
+        assert!(anchor.anchor_service_url.is_none());
+        assert!(anchor.auth_method.is_none());
+        assert!(anchor.ethereum_rpc_url.is_none());
+
+        cfg.anchor = Anchor::SelfHosted {
+            url: "http://cas:8081".to_string(),
+            auth_method: Some("shared-secret".to_string()),
+            private_seed_url: None,
+        };
+        cfg.ethereum_rpc_url = Some("http://ganache:8545".to_string());
+        let daemon = crate::daemon::DaemonConfig::try_from(cfg.clone()).unwrap();
+        let js = serde_json::to_string(&daemon).unwrap();
+        assert!(js.contains(r#""ethereum-rpc-url":"http://ganache:8545""#));
+        let daemon: crate::daemon::DaemonConfig = serde_json::from_str(&js).unwrap();
+        let import = import_daemon_config(daemon).unwrap();
+        assert!(import.dropped.is_empty());
+        assert_eq!(
+            serde_json::to_value(&cfg).unwrap(),
+            serde_json::to_value(&import.config).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_resolve_secrets_for_daemon_config() {
+        std::env::set_var(
+            "WHEEL_CONVERT_TEST_DB",
+            "postgres://ceramic:secret@db:5432/ceramic",
+        );
+        let mut cfg = Config::new(&NetworkIdentifier::InMemory, "test", None);
+        cfg.indexing.db = IndexingDb::Url(Secret::env("WHEEL_CONVERT_TEST_DB"));
+        let daemon = crate::daemon::DaemonConfig::try_from(cfg.clone()).unwrap();
+        assert_eq!(
+            daemon.indexing.unwrap().db.as_deref(),
+            Some("postgres://ceramic:secret@db:5432/ceramic")
+        );
+        assert_eq!(
+            serde_json::to_value(&cfg).unwrap()["indexing"]["db"]["Url"],
+            "env:WHEEL_CONVERT_TEST_DB"
+        );
+
+        cfg.indexing.db = IndexingDb::Postgres(crate::PostgresDb {
+            password: Some(Secret::env("WHEEL_CONVERT_TEST_UNSET")),
+            ..Default::default()
+        });
+        assert!(crate::daemon::DaemonConfig::try_from(cfg).is_err());
+    }
+
+    #[test]
+    fn should_reject_unknown_network() {
+        let daemon: crate::daemon::DaemonConfig =
+            serde_json::from_str(r#"{"network": {"name": "testnet-foo"}}"#).unwrap();
+        assert!(import_daemon_config(daemon).is_err());
     }
 }
@@ -1,15 +1,16 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum DaemonIpfsConfigMode {
     Bundled,
     Remote,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonIpfsConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -18,16 +19,20 @@ pub struct DaemonIpfsConfig {
     pub host: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pinning_endpoints: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_peer_data_sync: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum DaemonStateStoreMode {
     S3,
     File,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonStateStoreConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -40,7 +45,7 @@ pub struct DaemonStateStoreConfig {
     pub s3_endpoint: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonHttpApiConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -53,7 +58,7 @@ pub struct DaemonHttpApiConfig {
     pub admin_dids: Option<Vec<String>>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonNetworkConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -62,7 +67,7 @@ pub struct DaemonNetworkConfig {
     pub pubsub_topic: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonAnchorConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -73,7 +78,7 @@ pub struct DaemonAnchorConfig {
     pub ethereum_rpc_url: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonIndexingConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -84,14 +89,14 @@ pub struct DaemonIndexingConfig {
     pub enable_historical_sync: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonResolversConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub ethr_did_resolver: Option<HashMap<String, Value>>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonNodeConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -104,7 +109,7 @@ pub struct DaemonNodeConfig {
     pub private_seed_url: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonLoggerConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -115,16 +120,17 @@ pub struct DaemonLoggerConfig {
     pub log_to_files: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonMetricsConfig {
+    #[serde(default)]
     pub metrics_exporter_enabled: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub collector_host: Option<String>,
 }
 
 /// Config format compatible with existing ceramic daemon format
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DaemonConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -0,0 +1,97 @@
+use crate::Config;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A setting that differs between two configs, with secrets redacted
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "~ {}: {} -> {}", self.path, before, after),
+            (None, Some(after)) => write!(f, "+ {}: {}", self.path, after),
+            (Some(before), None) => write!(f, "- {}: {}", self.path, before),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+fn leaves(value: &Value, prefix: &str, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(m) if !m.is_empty() => {
+            for (k, v) in m {
+                let path = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                leaves(v, &path, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Settings changed from one config to another, by dotted path. Changed secrets are reported
+/// even when their redacted values are the same.
+pub fn diff(before: &Config, after: &Config) -> Vec<ConfigChange> {
+    let flatten = |doc: Value| {
+        let mut out = BTreeMap::new();
+        leaves(&doc, "", &mut out);
+        out
+    };
+    let raw_before = flatten(serde_json::to_value(before).unwrap_or_default());
+    let raw_after = flatten(serde_json::to_value(after).unwrap_or_default());
+    let shown_before = flatten(before.redacted());
+    let shown_after = flatten(after.redacted());
+    let paths: BTreeSet<_> = raw_before.keys().chain(raw_after.keys()).collect();
+    paths
+        .into_iter()
+        .filter(|p| raw_before.get(*p) != raw_after.get(*p))
+        .map(|p| ConfigChange {
+            path: p.clone(),
+            before: shown_before.get(p).cloned(),
+            after: shown_after.get(p).cloned(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndexingDb, PostgresDb, Secret};
+
+    #[test]
+    fn should_diff_changed_settings() {
+        let mut before = Config::default();
+        before.indexing.db = IndexingDb::Sqlite("/tmp/ceramic.db".into());
+        let mut after = before.clone();
+        after.http_api.port = 7008;
+        after.http_api.admin_dids.push("did:key:z6Mk".to_string());
+        after.indexing.db = IndexingDb::Postgres(PostgresDb {
+            password: Some(Secret::new("hunter2".to_string())),
+            ..Default::default()
+        });
+
+        let changes = diff(&before, &after);
+        let port = changes.iter().find(|c| c.path == "http_api.port").unwrap();
+        assert_eq!(port.to_string(), "~ http_api.port: 7007 -> 7008");
+        assert!(changes.iter().any(|c| c.path == "http_api.admin_dids"));
+        assert!(changes
+            .iter()
+            .any(|c| c.path == "indexing.db.Sqlite" && c.after.is_none()));
+        let password = changes
+            .iter()
+            .find(|c| c.path == "indexing.db.Postgres.password")
+            .unwrap();
+        assert!(!password.to_string().contains("hunter2"));
+        assert!(diff(&after, &after).is_empty());
+    }
+}
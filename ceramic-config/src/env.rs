@@ -0,0 +1,289 @@
+use crate::daemon::DaemonConfig;
+use crate::{Anchor, Config, IndexingDb, PostgresDb, Secret, SecretSource};
+use serde_json::Value;
+
+const ENV_PREFIX: &str = "CERAMIC";
+/// Stands in for a secret reference while the config is converted, so the reference can be
+/// rendered in its place. Only uses characters that are not changed by url encoding.
+const SECRET_MARKER: &str = "WHEELSECRETREF";
+
+/// Flavour of environment file to render
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnvFormat {
+    /// `KEY='value'` lines, as read by docker compose and dotenv libraries
+    Dotenv,
+    /// `export KEY="value"` lines, to be sourced by a shell
+    Shell,
+    /// `KEY=value` lines for `docker run --env-file`, values are used verbatim
+    DockerEnvFile,
+}
+
+impl std::fmt::Display for EnvFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dotenv => write!(f, "dotenv"),
+            Self::Shell => write!(f, "shell"),
+            Self::DockerEnvFile => write!(f, "docker"),
+        }
+    }
+}
+
+/// Daemon settings as `CERAMIC_*` environment variables
+pub struct EnvVars {
+    vars: Vec<(String, String)>,
+    references: Vec<(String, Secret)>,
+}
+
+impl EnvVars {
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Secret references that cannot be expanded by the format, and are rendered as is
+    pub fn unexpanded_references(&self, format: EnvFormat) -> Vec<&Secret> {
+        self.references
+            .iter()
+            .filter(|(_, s)| expand_reference(s, format).is_none())
+            .map(|(_, s)| s)
+            .collect()
+    }
+
+    pub fn render(&self, format: EnvFormat) -> anyhow::Result<String> {
+        let mut out = String::default();
+        for (key, value) in &self.vars {
+            let has_reference = value.contains(SECRET_MARKER);
+            let quoted = match format {
+                EnvFormat::Dotenv if !has_reference && !value.contains('\'') => {
+                    format!("'{}'", value)
+                }
+                EnvFormat::Dotenv | EnvFormat::Shell => format!("\"{}\"", escape(value)),
+                EnvFormat::DockerEnvFile => {
+                    if value.contains('\n') {
+                        anyhow::bail!(
+                            "{} contains a newline, which docker env files do not support",
+                            key
+                        );
+                    }
+                    value.clone()
+                }
+            };
+            let value = self.references.iter().fold(quoted, |v, (marker, secret)| {
+                let reference = expand_reference(secret, format)
+                    .unwrap_or_else(|| secret.reference().to_string());
+                v.replace(marker, &reference)
+            });
+            if format == EnvFormat::Shell {
+                out.push_str("export ");
+            }
+            out.push_str(&format!("{}={}\n", key, value));
+        }
+        Ok(out)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+        .replace('`', "\\`")
+}
+
+/// Reference rendered so the format reads it when the file is used
+fn expand_reference(secret: &Secret, format: EnvFormat) -> Option<String> {
+    match (secret.source(), format) {
+        (SecretSource::Env(name), EnvFormat::Dotenv | EnvFormat::Shell) => {
+            Some(format!("${{{}}}", name))
+        }
+        (SecretSource::File(path), EnvFormat::Shell) => {
+            Some(format!("$(cat '{}')", path.replace('\'', "'\\''")))
+        }
+        _ => None,
+    }
+}
+
+fn env_name(segments: &[&str]) -> String {
+    let mut name = ENV_PREFIX.to_string();
+    for s in segments {
+        name.push('_');
+        name.push_str(&s.replace('-', "_").to_uppercase());
+    }
+    name
+}
+
+fn env_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) if items.iter().all(Value::is_string) => Some(
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Replace secret references with markers, so they are not resolved by the conversion
+fn mark_references(cfg: &mut Config) -> Vec<(String, Secret)> {
+    let mut references = vec![];
+    let mut mark = |secret: &mut Secret| {
+        if !secret.is_literal() {
+            let marker = format!("{}{}_", SECRET_MARKER, references.len());
+            let reference = std::mem::replace(secret, Secret::new(marker.clone()));
+            references.push((marker, reference));
+        }
+    };
+    match &mut cfg.anchor {
+        Anchor::RemoteDid {
+            private_seed_url, ..
+        } => mark(private_seed_url),
+        Anchor::SelfHosted {
+            private_seed_url: Some(private_seed_url),
+            ..
+        } => mark(private_seed_url),
+        _ => {}
+    }
+    match &mut cfg.indexing.db {
+        IndexingDb::Postgres(PostgresDb {
+            password: Some(password),
+            ..
+        }) => mark(password),
+        IndexingDb::Url(url) => mark(url),
+        _ => {}
+    }
+    references
+}
+
+/// The admin seed and indexing database url of a daemon config, as the `CERAMIC_*` variables
+/// generated deployments pass to the daemon
+pub(crate) struct DaemonSecrets {
+    pub vars: Vec<(String, String)>,
+    /// Environment variables the values reference, for the deployment to provide
+    pub env_references: Vec<String>,
+}
+
+impl Config {
+    /// The daemon config without the admin seed and indexing database url, which are returned as
+    /// variables instead so generated deployments keep them out of config files. `env:`
+    /// references are rendered as `${NAME}` rather than resolved, and `escape` is applied to the
+    /// rest of each value. `file:` references cannot be read by a deployment and fail.
+    pub(crate) fn split_secrets(
+        &self,
+        escape: fn(&str) -> String,
+    ) -> anyhow::Result<(DaemonConfig, DaemonSecrets)> {
+        let mut cfg = self.clone();
+        let references = mark_references(&mut cfg);
+        let mut daemon = DaemonConfig::try_from(cfg)?;
+        let mut vars = vec![];
+        if let Some(seed) = daemon.node.as_mut().and_then(|n| n.private_seed_url.take()) {
+            vars.push((env_name(&["node", "private-seed-url"]), seed));
+        }
+        if !matches!(self.indexing.db, IndexingDb::Sqlite(_)) {
+            if let Some(db) = daemon.indexing.as_mut().and_then(|i| i.db.take()) {
+                vars.push((env_name(&["indexing", "db"]), db));
+            }
+        }
+        let mut env_references = vec![];
+        for (_, value) in vars.iter_mut() {
+            let mut escaped = escape(value);
+            for (marker, secret) in &references {
+                if !escaped.contains(marker.as_str()) {
+                    continue;
+                }
+                let reference = match secret.source() {
+                    SecretSource::Env(name) => {
+                        env_references.push(name.to_string());
+                        format!("${{{}}}", name)
+                    }
+                    SecretSource::File(path) => anyhow::bail!(
+                        "Secret file {} cannot be read by a generated deployment, use an env: reference instead",
+                        path
+                    ),
+                    SecretSource::Literal(value) => value.to_string(),
+                };
+                escaped = escaped.replace(marker.as_str(), &reference);
+            }
+            *value = escaped;
+        }
+        Ok((
+            daemon,
+            DaemonSecrets {
+                vars,
+                env_references,
+            },
+        ))
+    }
+
+    /// The daemon config as `CERAMIC_<SECTION>_<SETTING>` variables. Secrets are resolved, unless
+    /// `keep_secret_references` is set, in which case `env:` and `file:` references are rendered
+    /// for the output format to read. These are named after daemon config sections and are not
+    /// read back by [`crate::ConfigLayers`], which only takes `__` separated wheel config paths.
+    pub fn env_vars(&self, keep_secret_references: bool) -> anyhow::Result<EnvVars> {
+        let mut cfg = self.clone();
+        let references = if keep_secret_references {
+            mark_references(&mut cfg)
+        } else {
+            vec![]
+        };
+        let doc = serde_json::to_value(DaemonConfig::try_from(cfg)?)?;
+        let mut vars = vec![];
+        if let Value::Object(sections) = doc {
+            for (section, settings) in &sections {
+                match settings {
+                    Value::Object(settings) => {
+                        for (setting, value) in settings {
+                            if let Some(v) = env_value(value) {
+                                vars.push((env_name(&[section, setting]), v));
+                            }
+                        }
+                    }
+                    other => {
+                        if let Some(v) = env_value(other) {
+                            vars.push((env_name(&[section]), v));
+                        }
+                    }
+                }
+            }
+        }
+        vars.sort();
+        Ok(EnvVars { vars, references })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_render_env_formats() {
+        let mut cfg = Config::new(&crate::NetworkIdentifier::Clay, "test", None);
+        cfg.anchor = Anchor::RemoteDid {
+            url: "https://cas-clay.3boxlabs.com".to_string(),
+            private_seed_url: Secret::env("CERAMIC_SEED"),
+        };
+        cfg.indexing.db = IndexingDb::Postgres(PostgresDb {
+            password: Some(Secret::file("/run/secrets/db")),
+            ..Default::default()
+        });
+        cfg.http_api.cors_allowed_origins = vec!["a".to_string(), "b".to_string()];
+        let env = cfg.env_vars(true).unwrap();
+        let vars: std::collections::HashMap<_, _> = env.vars().collect();
+        assert_eq!(vars["CERAMIC_HTTP_API_PORT"], "7007");
+        assert_eq!(vars["CERAMIC_HTTP_API_CORS_ALLOWED_ORIGINS"], "a,b");
+
+        let shell = env.render(EnvFormat::Shell).unwrap();
+        assert!(shell.contains("export CERAMIC_NODE_PRIVATE_SEED_URL=\"${CERAMIC_SEED}\"\n"));
+        assert!(shell.contains(
+            "export CERAMIC_INDEXING_DB=\"postgres://ceramic:$(cat '/run/secrets/db')@localhost:5432/ceramic\"\n"
+        ));
+        let docker = env.render(EnvFormat::DockerEnvFile).unwrap();
+        assert!(docker.contains("CERAMIC_NODE_PRIVATE_SEED_URL=env:CERAMIC_SEED\n"));
+        assert_eq!(env.unexpanded_references(EnvFormat::DockerEnvFile).len(), 2);
+        assert_eq!(env.unexpanded_references(EnvFormat::Shell).len(), 0);
+
+        assert!(cfg.env_vars(false).is_err());
+    }
+}
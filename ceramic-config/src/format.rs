@@ -0,0 +1,138 @@
+use crate::Config;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Config file names searched for in a project directory, in order of preference
+pub const CONFIG_FILE_NAMES: &[&str] = &[
+    "ceramic.json",
+    "ceramic.toml",
+    "ceramic.yaml",
+    "ceramic.yml",
+];
+
+#[derive(Serialize)]
+struct WithSchema<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'a str,
+    #[serde(flatten)]
+    config: &'a Config,
+}
+
+/// File format of a wheel config, chosen by file extension
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl std::fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Toml => write!(f, "toml"),
+            Self::Yaml => write!(f, "yaml"),
+        }
+    }
+}
+
+impl ConfigFormat {
+    /// Format for a file, files without a recognized extension are treated as json
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::Toml,
+            Some("yaml") | Some("yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Parse a config document without interpreting it as a config
+    pub fn parse(&self, data: &[u8]) -> anyhow::Result<Value> {
+        let value = match self {
+            Self::Json => serde_json::from_slice(data)?,
+            Self::Toml => toml::from_str(std::str::from_utf8(data)?)?,
+            Self::Yaml => serde_yaml::from_slice(data)?,
+        };
+        Ok(value)
+    }
+
+    /// Render a config with a `$schema` reference, as a property for json and as the comment
+    /// understood by editor tooling for toml and yaml
+    pub fn render_with_schema(&self, cfg: &Config, schema: &str) -> anyhow::Result<String> {
+        let s = match self {
+            Self::Json => serde_json::to_string_pretty(&WithSchema {
+                schema,
+                config: cfg,
+            })?,
+            Self::Toml => format!("#:schema {}\n{}", schema, self.render(cfg)?),
+            Self::Yaml => format!(
+                "# yaml-language-server: $schema={}\n{}",
+                schema,
+                self.render(cfg)?
+            ),
+        };
+        Ok(s)
+    }
+
+    pub fn render(&self, cfg: &Config) -> anyhow::Result<String> {
+        let s = match self {
+            Self::Json => serde_json::to_string_pretty(cfg)?,
+            Self::Toml => toml::to_string_pretty(cfg)?,
+            // serde_yaml writes enums as yaml tags, go through json to keep the same shape
+            Self::Yaml => serde_yaml::to_string(&serde_json::to_value(cfg)?)?,
+        };
+        Ok(s)
+    }
+}
+
+/// Find an existing config file in a directory, defaulting to `ceramic.json`
+pub fn find_config_file(directory: impl AsRef<Path>) -> PathBuf {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|n| directory.as_ref().join(n))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| directory.as_ref().join(CONFIG_FILE_NAMES[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{migrate, Anchor, CasAuth, NetworkIdentifier};
+
+    #[test]
+    fn should_roundtrip_all_formats() {
+        let mut cfg = Config::new(
+            &NetworkIdentifier::Local,
+            "test",
+            Some(CasAuth {
+                url: "https://cas-qa.3boxlabs.com".to_string(),
+                pk: Some("inplace:ed25519#00".to_string()),
+            }),
+        );
+        cfg.logger.file = None;
+        let expected = serde_json::to_value(&cfg).unwrap();
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let rendered = format
+                .render_with_schema(&cfg, crate::CONFIG_SCHEMA_FILE)
+                .unwrap();
+            let parsed = migrate(format.parse(rendered.as_bytes()).unwrap()).unwrap();
+            assert!(!parsed.was_migrated());
+            assert!(matches!(parsed.config.anchor, Anchor::RemoteDid { .. }));
+            assert_eq!(
+                serde_json::to_value(&parsed.config).unwrap(),
+                expected,
+                "{} did not roundtrip",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn should_choose_format_by_extension() {
+        assert_eq!(ConfigFormat::from_path("ceramic.toml"), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path("a/ceramic.yml"), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path("ceramic.json"), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::from_path("ceramic"), ConfigFormat::Json);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{migrate::migrate_value, Config, ConfigFormat, NetworkRegistry};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable prefixes, in increasing order of precedence
+const ENV_PREFIXES: &[&str] = &["CERAMIC_", "WHEEL_"];
+/// Separator between path segments in environment variable names, e.g. `WHEEL_HTTP_API__PORT`
+const ENV_SEPARATOR: &str = "__";
+
+/// Layer an effective config value came from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Flag,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(p) => write!(f, "file {}", p.display()),
+            Self::Env(var) => write!(f, "env {}", var),
+            Self::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// Effective config, with the layer each value came from keyed by dotted path
+pub struct LayeredConfig {
+    pub config: Config,
+    pub sources: BTreeMap<String, ConfigSource>,
+}
+
+impl LayeredConfig {
+    /// Values that were set by the environment or flags
+    pub fn overrides(&self) -> impl Iterator<Item = (&String, &ConfigSource)> {
+        self.sources
+            .iter()
+            .filter(|(_, s)| matches!(s, ConfigSource::Env(_) | ConfigSource::Flag))
+    }
+}
+
+/// Environment and flag layers applied on top of defaults and a config file
+#[derive(Clone, Debug, Default)]
+pub struct ConfigLayers {
+    env: Vec<(String, String)>,
+    flags: Vec<(String, String)>,
+}
+
+impl ConfigLayers {
+    /// Layers using the `CERAMIC_*` and `WHEEL_*` variables of the current process
+    pub fn from_env() -> Self {
+        Self::default().with_env(std::env::vars())
+    }
+
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut env: Vec<_> = vars
+            .into_iter()
+            .filter(|(k, _)| ENV_PREFIXES.iter().any(|p| k.starts_with(p)))
+            .collect();
+        env.sort_by_key(|(k, _)| {
+            let prefix = ENV_PREFIXES.iter().position(|p| k.starts_with(p));
+            (prefix, k.clone())
+        });
+        self.env = env;
+        self
+    }
+
+    /// Set a dotted config path, e.g. `http_api.port`, to a value. Flags take precedence over
+    /// the environment.
+    pub fn with_flag(mut self, path: impl Into<String>, value: impl Into<String>) -> Self {
+        self.flags.push((path.into(), value.into()));
+        self
+    }
+
+    /// Load defaults, then the config file, then environment and flag layers
+    pub fn load_file(&self, file: impl AsRef<Path>) -> anyhow::Result<LayeredConfig> {
+        let data = std::fs::read(file.as_ref())?;
+        let mut raw = ConfigFormat::from_path(file.as_ref()).parse(data.as_slice())?;
+        if let Some(m) = raw.as_object_mut() {
+            m.remove("$schema");
+        }
+        let mut sources = BTreeMap::new();
+        let file_source = ConfigSource::File(file.as_ref().to_path_buf());
+        let mut doc = serde_json::to_value(Config::default())?;
+        record_leaves(&doc, "", &ConfigSource::Default, &mut sources);
+        merge(&mut doc, migrate_value(raw.clone())?.0);
+        record_leaves(&raw, "", &file_source, &mut sources);
+        self.apply_layers(&mut doc, &mut sources)?;
+        prune_sources(&doc, &mut sources);
+        Ok(LayeredConfig {
+            config: serde_json::from_value(doc)?,
+            sources,
+        })
+    }
+
+    /// Apply environment and flag layers to an already loaded config
+    pub fn apply(&self, cfg: &Config, base: ConfigSource) -> anyhow::Result<LayeredConfig> {
+        let mut doc = serde_json::to_value(cfg)?;
+        let mut sources = BTreeMap::new();
+        record_leaves(&doc, "", &base, &mut sources);
+        self.apply_layers(&mut doc, &mut sources)?;
+        prune_sources(&doc, &mut sources);
+        Ok(LayeredConfig {
+            config: serde_json::from_value(doc)?,
+            sources,
+        })
+    }
+
+    fn apply_layers(
+        &self,
+        doc: &mut Value,
+        sources: &mut BTreeMap<String, ConfigSource>,
+    ) -> anyhow::Result<()> {
+        for (var, value) in &self.env {
+            let name = ENV_PREFIXES
+                .iter()
+                .find_map(|p| var.strip_prefix(p))
+                .unwrap_or(var);
+            // only nested names are read, single segment names like CERAMIC_NETWORK are used by
+            // the daemon and other tools
+            if !name.contains(ENV_SEPARATOR) {
+                log::debug!("Ignoring {}, not a nested config setting", var);
+                continue;
+            }
+            let path = name.replace(ENV_SEPARATOR, ".");
+            if resolve_path(doc, &path).is_none() {
+                log::debug!("Ignoring {}, not a config setting", var);
+                continue;
+            }
+            let segments: Vec<_> = name.split(ENV_SEPARATOR).collect();
+            let mut updated = doc.clone();
+            let path = set_path(&mut updated, &segments, value)?;
+            if let Err(e) = serde_json::from_value::<Config>(updated.clone()) {
+                log::warn!("Ignoring {}, invalid value for {}: {}", var, path, e);
+                continue;
+            }
+            *doc = updated;
+            replace_sources(doc, &path, ConfigSource::Env(var.clone()), sources);
+        }
+        for (path, value) in &self.flags {
+            let segments: Vec<_> = path.split('.').collect();
+            if !has_key(doc, segments[0]) {
+                anyhow::bail!("Unknown config setting {}", path);
+            }
+            let path = set_path(doc, &segments, value)?;
+            replace_sources(doc, &path, ConfigSource::Flag, sources);
+        }
+        Ok(())
+    }
+}
+
+fn is_enum_variant(m: &Map<String, Value>) -> bool {
+    m.len() == 1
+        && m.keys()
+            .all(|k| k.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+/// Deep merge a document over another, replacing values that are not both objects. Objects
+/// holding different enum variants replace each other rather than merging.
+fn merge(base: &mut Value, over: Value) {
+    match (base, over) {
+        (Value::Object(b), Value::Object(o))
+            if !(is_enum_variant(b) && is_enum_variant(&o) && b.keys().ne(o.keys())) =>
+        {
+            for (k, v) in o {
+                match b.get_mut(&k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        b.insert(k, v);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn record_leaves(
+    value: &Value,
+    prefix: &str,
+    source: &ConfigSource,
+    sources: &mut BTreeMap<String, ConfigSource>,
+) {
+    match value {
+        Value::Object(m) if !m.is_empty() => {
+            for (k, v) in m {
+                record_leaves(v, &join_path(prefix, k), source, sources);
+            }
+        }
+        _ => {
+            sources.insert(prefix.to_string(), source.clone());
+        }
+    }
+}
+
+fn replace_sources(
+    doc: &Value,
+    path: &str,
+    source: ConfigSource,
+    sources: &mut BTreeMap<String, ConfigSource>,
+) {
+    let nested = format!("{}.", path);
+    sources.retain(|k, _| {
+        k != path && !k.starts_with(&nested) && !path.starts_with(&format!("{}.", k))
+    });
+    let value = lookup(doc, path).unwrap_or(&Value::Null);
+    record_leaves(value, path, &source, sources);
+}
+
+/// Drop sources for values replaced by a later layer, either no longer present or replaced by
+/// an object with values of its own
+fn prune_sources(doc: &Value, sources: &mut BTreeMap<String, ConfigSource>) {
+    let parents: Vec<String> = sources
+        .keys()
+        .filter(|k| {
+            let nested = format!("{}.", k);
+            sources.keys().any(|other| other.starts_with(&nested))
+        })
+        .cloned()
+        .collect();
+    sources.retain(|path, _| !parents.contains(path) && lookup(doc, path).is_some());
+}
+
+/// Value at a dotted path
+pub fn lookup<'a>(doc: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(doc, |v, k| v.get(k))
+}
+
+fn find_key(m: &Map<String, Value>, segment: &str) -> Option<String> {
+    m.keys().find(|k| k.eq_ignore_ascii_case(segment)).cloned()
+}
+
+fn has_key(doc: &Value, segment: &str) -> bool {
+    doc.as_object()
+        .map(|m| find_key(m, segment).is_some())
+        .unwrap_or(false)
+}
+
+/// Set the value at a path of case insensitive segments, returning the resolved path. Values are
+/// parsed as json, but scalars replacing a string are kept as strings.
+fn set_path(doc: &mut Value, segments: &[&str], raw: &str) -> anyhow::Result<String> {
+    let mut current = doc;
+    let mut path = String::default();
+    for segment in segments {
+        if segment.is_empty() {
+            anyhow::bail!("Invalid config path {}", segments.join("."));
+        }
+        if !current.is_object() {
+            *current = Value::Object(Map::default());
+        }
+        let m = current.as_object_mut().unwrap();
+        let key = find_key(m, segment).unwrap_or_else(|| segment.to_lowercase());
+        path = join_path(&path, &key);
+        current = m.entry(key).or_insert(Value::Null);
+    }
+    *current = match serde_json::from_str::<Value>(raw) {
+        Ok(v) if v.is_object() || v.is_array() || !current.is_string() => v,
+        _ => Value::String(raw.to_string()),
+    };
+    Ok(path)
+}
+
+/// Resolve a dotted path against a document using case insensitive segments
+fn resolve_path(doc: &Value, path: &str) -> Option<String> {
+    let mut current = doc;
+    let mut resolved = String::default();
+    for segment in path.split('.') {
+        let key = find_key(current.as_object()?, segment)?;
+        resolved = join_path(&resolved, &key);
+        current = &current[&key];
+    }
+    Some(resolved)
+}
+
+/// Parse a document back into a config, failing if the setting at `path` did not survive, e.g.
+/// because it is not a field the config knows about
+fn to_config(doc: Value, path: &str) -> anyhow::Result<Config> {
+    let cfg: Config = serde_json::from_value(doc)
+        .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", path, e))?;
+    let value = serde_json::to_value(&cfg)?;
+    let parent = path.rsplit_once('.').map(|(p, _)| p);
+    if lookup(&value, path).is_none() && parent.and_then(|p| lookup(&value, p)).is_some() {
+        anyhow::bail!("Unknown config setting {}", path);
+    }
+    Ok(cfg)
+}
+
+/// Set a dotted config path, e.g. `http_api.port`, to a value parsed the same way as flags.
+/// Returns the updated config and the resolved path.
+pub fn set_setting(cfg: &Config, path: &str, raw: &str) -> anyhow::Result<(Config, String)> {
+    let mut doc = serde_json::to_value(cfg)?;
+    let segments: Vec<_> = path.split('.').collect();
+    if !has_key(&doc, segments[0]) {
+        anyhow::bail!("Unknown config setting {}", path);
+    }
+    let path = set_path(&mut doc, &segments, raw)?;
+    Ok((to_config(doc, &path)?, path))
+}
+
+/// Reset a dotted config path to its default for the config's network, removing it if there is
+/// no default, e.g. for optional settings. Returns the updated config and the resolved path.
+pub fn unset_setting(cfg: &Config, path: &str) -> anyhow::Result<(Config, String)> {
+    let mut doc = serde_json::to_value(cfg)?;
+    let resolved = match resolve_path(&doc, path) {
+        Some(p) => p,
+        None => anyhow::bail!("Config setting {} is not set", path),
+    };
+    if resolved.split('.').next() == Some("network") {
+        anyhow::bail!(
+            "Config setting {} cannot be unset, run wheel setup to change the network",
+            resolved
+        );
+    }
+    let mut defaults =
+        Config::for_network(&NetworkRegistry::for_identifier(&cfg.network.id), "", None);
+    defaults.network = cfg.network.clone();
+    let defaults = serde_json::to_value(defaults)?;
+    match lookup(&defaults, &resolved) {
+        Some(default) => {
+            let target = resolved
+                .split('.')
+                .try_fold(&mut doc, |v, k| v.get_mut(k))
+                .expect("path was resolved");
+            *target = default.clone();
+        }
+        None => {
+            let (parent, key) = match resolved.rsplit_once('.') {
+                Some((parent, key)) => (
+                    parent
+                        .split('.')
+                        .try_fold(&mut doc, |v, k| v.get_mut(k))
+                        .expect("path was resolved"),
+                    key,
+                ),
+                None => (&mut doc, resolved.as_str()),
+            };
+            if let Some(m) = parent.as_object_mut() {
+                m.remove(key);
+            }
+        }
+    }
+    Ok((to_config(doc, &resolved)?, resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_apply_env_then_flags() {
+        let layers = ConfigLayers::default()
+            .with_env(vec![
+                ("WHEEL_HTTP_API__PORT".to_string(), "7008".to_string()),
+                ("CERAMIC_HTTP_API__PORT".to_string(), "7009".to_string()),
+                (
+                    "WHEEL_INDEXING__DB".to_string(),
+                    "postgres://ceramic@db/ceramic".to_string(),
+                ),
+                ("CERAMIC_URL".to_string(), "http://localhost".to_string()),
+                ("HOME".to_string(), "/root".to_string()),
+            ])
+            .with_flag("http_api.hostname", "0.0.0.0");
+        let cfg = Config::default();
+        let layered = layers
+            .apply(&cfg, ConfigSource::File(PathBuf::from("ceramic.json")))
+            .unwrap();
+
+        assert_eq!(layered.config.http_api.port, 7008);
+        assert_eq!(
+            layered.config.indexing.db.url().unwrap(),
+            "postgres://ceramic@db:5432/ceramic"
+        );
+        assert_eq!(layered.config.http_api.hostname, "0.0.0.0");
+        assert_eq!(
+            layered.sources["http_api.port"],
+            ConfigSource::Env("WHEEL_HTTP_API__PORT".to_string())
+        );
+        assert_eq!(layered.sources["http_api.hostname"], ConfigSource::Flag);
+        assert_eq!(
+            layered.sources["node.gateway"],
+            ConfigSource::File(PathBuf::from("ceramic.json"))
+        );
+        assert_eq!(layered.overrides().count(), 3);
+    }
+
+    #[test]
+    fn should_replace_enum_variant() {
+        let layers = ConfigLayers::default().with_flag("metrics", r#"{"Enabled":"http://otel"}"#);
+        let layered = layers
+            .apply(&Config::default(), ConfigSource::Default)
+            .unwrap();
+        assert!(
+            matches!(layered.config.metrics, crate::Metrics::Enabled(ref h) if h == "http://otel")
+        );
+        assert_eq!(layered.sources["metrics.Enabled"], ConfigSource::Flag);
+    }
+
+    #[test]
+    fn should_layer_partial_file_over_defaults() {
+        let file = std::env::temp_dir().join("wheel-layers-partial.json");
+        std::fs::write(
+            &file,
+            r#"{"version": 1, "http_api": {"port": 7010}, "state_store": {"S3": {"bucket": "b", "endpoint": ""}}}"#,
+        )
+        .unwrap();
+        let layered = ConfigLayers::default().load_file(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(layered.config.http_api.port, 7010);
+        assert_eq!(layered.config.http_api.hostname, "127.0.0.1");
+        assert!(matches!(
+            layered.config.state_store,
+            crate::StateStore::S3(_)
+        ));
+        assert_eq!(
+            layered.sources["http_api.port"],
+            ConfigSource::File(file.clone())
+        );
+        assert_eq!(layered.sources["http_api.hostname"], ConfigSource::Default);
+        assert!(!layered.sources.contains_key("state_store.LocalDirectory"));
+    }
+
+    #[test]
+    fn should_reject_unknown_flag() {
+        let layers = ConfigLayers::default().with_flag("http.port", "7008");
+        assert!(layers
+            .apply(&Config::default(), ConfigSource::Default)
+            .is_err());
+    }
+
+    #[test]
+    fn should_set_and_unset_setting() {
+        let cfg = Config::default();
+        let (cfg, path) = set_setting(&cfg, "HTTP_API.Port", "7010").unwrap();
+        assert_eq!(path, "http_api.port");
+        assert_eq!(cfg.http_api.port, 7010);
+
+        assert!(set_setting(&cfg, "http_api.prot", "7011").is_err());
+        assert!(set_setting(&cfg, "http_api.port", "not a port").is_err());
+
+        let (cfg, path) = unset_setting(&cfg, "http_api.port").unwrap();
+        assert_eq!(path, "http_api.port");
+        assert_eq!(cfg.http_api.port, Config::default().http_api.port);
+        assert!(unset_setting(&cfg, "http_api.prot").is_err());
+    }
+
+    #[test]
+    fn should_unset_to_network_defaults() {
+        let mut cfg = Config::new(&crate::NetworkIdentifier::Mainnet, "test", None);
+        cfg.indexing.enable_historical_sync = false;
+        let (cfg, _) = unset_setting(&cfg, "indexing.enable_historical_sync").unwrap();
+        assert!(cfg.indexing.enable_historical_sync);
+        assert!(unset_setting(&cfg, "network.id").is_err());
+        assert!(unset_setting(&cfg, "network").is_err());
+    }
+
+    #[test]
+    fn should_ignore_env_that_is_not_a_nested_setting() {
+        let layers = ConfigLayers::default().with_env(vec![
+            ("CERAMIC_NETWORK".to_string(), "mainnet".to_string()),
+            ("CERAMIC_VERSION".to_string(), "2.3.0".to_string()),
+            ("CERAMIC_IPFS".to_string(), "http://ipfs:5001".to_string()),
+            ("WHEEL_HTTP_API__PROT".to_string(), "7008".to_string()),
+            (
+                "WHEEL_NODE__STREAM_CACHE_LIMIT".to_string(),
+                "lots".to_string(),
+            ),
+            ("WHEEL_HTTP_API__PORT".to_string(), "7008".to_string()),
+        ]);
+        let layered = layers
+            .apply(&Config::default(), ConfigSource::Default)
+            .unwrap();
+        assert_eq!(layered.config.http_api.port, 7008);
+        assert_eq!(
+            layered.config.node.stream_cache_limit,
+            Config::default().node.stream_cache_limit
+        );
+        assert_eq!(layered.overrides().count(), 1);
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{
+    Anchor, Config, Diagnostic, IndexingDb, Metrics, NetworkIdentifier, PostgresDb, Secret,
+    Severity, StateStore,
+};
+
+/// A lint rule violation
+#[derive(Clone, Debug)]
+pub struct LintFinding {
+    pub rule: &'static str,
+    pub diagnostic: Diagnostic,
+}
+
+impl std::fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.rule, self.diagnostic)
+    }
+}
+
+/// Findings from linting a config against the production mainnet profile
+#[derive(Clone, Debug, Default)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    fn push(
+        &mut self,
+        rule: &'static str,
+        severity: Severity,
+        path: &str,
+        message: impl Into<String>,
+    ) {
+        self.findings.push(LintFinding {
+            rule,
+            diagnostic: Diagnostic {
+                path: path.to_string(),
+                severity,
+                message: message.into(),
+            },
+        });
+    }
+
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &LintFinding> {
+        self.findings
+            .iter()
+            .filter(move |f| f.diagnostic.severity == severity)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.with_severity(Severity::Error).next().is_some()
+    }
+
+    /// Score out of 100, errors cost 25 points and warnings 10
+    pub fn score(&self) -> u32 {
+        let penalty: u32 = self
+            .findings
+            .iter()
+            .map(|f| match f.diagnostic.severity {
+                Severity::Error => 25,
+                Severity::Warning => 10,
+            })
+            .sum();
+        100u32.saturating_sub(penalty)
+    }
+}
+
+fn is_unspecified_address(hostname: &str) -> bool {
+    hostname
+        .parse::<std::net::IpAddr>()
+        .map(|ip| ip.is_unspecified())
+        .unwrap_or(false)
+}
+
+impl Config {
+    /// Check the config against the recommended settings for a production mainnet node
+    pub fn lint(&self) -> LintReport {
+        let mut report = LintReport::default();
+        let mainnet = self.network.id == NetworkIdentifier::Mainnet;
+
+        if self.indexing.is_sqlite() {
+            report.push(
+                "no-sqlite",
+                Severity::Error,
+                "indexing.db",
+                "sqlite is not supported for production, use postgres",
+            );
+        }
+        if mainnet && !self.indexing.enable_historical_sync {
+            report.push(
+                "historical-sync",
+                Severity::Warning,
+                "indexing.enable_historical_sync",
+                "mainnet nodes should enable historical sync",
+            );
+        }
+
+        match &self.anchor {
+            Anchor::Ip { .. } => report.push(
+                "did-anchor-auth",
+                Severity::Error,
+                "anchor",
+                "IP authentication with the anchor service is deprecated, use DID authentication",
+            ),
+            Anchor::None if self.network.id != NetworkIdentifier::InMemory => report.push(
+                "anchor-required",
+                Severity::Error,
+                "anchor",
+                format!("streams on {} will not be anchored", self.network.id),
+            ),
+            _ => {}
+        }
+
+        let private_seed_url = match &self.anchor {
+            Anchor::RemoteDid {
+                private_seed_url, ..
+            } => Some(private_seed_url),
+            Anchor::SelfHosted {
+                private_seed_url, ..
+            } => private_seed_url.as_ref(),
+            _ => None,
+        };
+        if private_seed_url.map(Secret::is_literal).unwrap_or(false) {
+            report.push(
+                "secret-references",
+                Severity::Warning,
+                "anchor.private_seed_url",
+                "private seed is stored in the config, use an env: or file: reference",
+            );
+        }
+        if let IndexingDb::Postgres(PostgresDb {
+            password: Some(password),
+            ..
+        }) = &self.indexing.db
+        {
+            if password.is_literal() {
+                report.push(
+                    "secret-references",
+                    Severity::Warning,
+                    "indexing.db.Postgres.password",
+                    "database password is stored in the config, use an env: or file: reference",
+                );
+            }
+        }
+
+        if self
+            .http_api
+            .cors_allowed_origins
+            .iter()
+            .any(|o| o == ".*" || o == "*")
+        {
+            report.push(
+                "restrict-cors",
+                Severity::Warning,
+                "http_api.cors_allowed_origins",
+                "all origins are allowed, restrict cors to known origins",
+            );
+        }
+        if is_unspecified_address(&self.http_api.hostname) && self.http_api.admin_dids.is_empty() {
+            report.push(
+                "admin-dids",
+                Severity::Error,
+                "http_api.admin_dids",
+                format!(
+                    "http api is bound to {} with no admin dids",
+                    self.http_api.hostname
+                ),
+            );
+        }
+
+        if mainnet {
+            if let StateStore::LocalDirectory(_) = &self.state_store {
+                report.push(
+                    "durable-state-store",
+                    Severity::Warning,
+                    "state_store",
+                    "local directory state store is not durable, consider S3",
+                );
+            }
+        }
+
+        if let Metrics::Disabled = &self.metrics {
+            report.push(
+                "metrics",
+                Severity::Warning,
+                "metrics",
+                "metrics are disabled, enable them to monitor the node",
+            );
+        }
+
+        if let Some(file) = &self.logger.file {
+            if file.enabled && file.directory.is_relative() {
+                report.push(
+                    "absolute-log-directory",
+                    Severity::Warning,
+                    "logger.file.directory",
+                    format!(
+                        "{} is relative to where ceramic is started, use an absolute path",
+                        file.directory.display()
+                    ),
+                );
+            }
+        }
+
+        // validation problems a dedicated rule already reports are not counted twice, unless the
+        // rule is less severe and would hide an error
+        let covered: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.diagnostic.path.clone(), f.diagnostic.severity))
+            .collect();
+        let invalid: Vec<_> = self
+            .validate()
+            .into_iter()
+            .filter(|d| {
+                !covered
+                    .iter()
+                    .any(|(path, severity)| *path == d.path && *severity >= d.severity)
+            })
+            .map(|d| LintFinding {
+                rule: "valid-config",
+                diagnostic: d,
+            })
+            .collect();
+        report.findings.splice(0..0, invalid);
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_lint_default_config() {
+        let mut cfg = Config::new(&NetworkIdentifier::Mainnet, "test", None);
+        cfg.indexing.db = IndexingDb::Sqlite("/tmp/ceramic.db".into());
+        cfg.http_api.hostname = "0.0.0.0".to_string();
+        let report = cfg.lint();
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule).collect();
+        for rule in [
+            "no-sqlite",
+            "anchor-required",
+            "admin-dids",
+            "durable-state-store",
+            "metrics",
+            "absolute-log-directory",
+        ] {
+            assert!(rules.contains(&rule), "missing {}", rule);
+        }
+        assert!(report.has_errors());
+        assert_eq!(report.score(), 0);
+    }
+
+    #[test]
+    fn should_pass_production_config() {
+        let mut cfg = Config::new(
+            &NetworkIdentifier::Mainnet,
+            "test",
+            Some(crate::CasAuth {
+                url: "https://cas.3boxlabs.com".to_string(),
+                pk: Some("inplace:ed25519#00".to_string()),
+            }),
+        );
+        cfg.state_store = StateStore::S3(crate::S3StateStore {
+            bucket: "ceramic".to_string(),
+            endpoint: String::default(),
+        });
+        cfg.metrics = Metrics::Enabled("http://localhost:4318".to_string());
+        cfg.logger.file = Some(crate::FileLogger {
+            enabled: true,
+            directory: "/var/log/ceramic".into(),
+        });
+        cfg.anchor = crate::Anchor::RemoteDid {
+            url: "https://cas.3boxlabs.com".to_string(),
+            private_seed_url: Secret::file("/run/secrets/ceramic-seed"),
+        };
+        cfg.indexing.db = IndexingDb::Postgres(PostgresDb {
+            password: Some(Secret::env("CERAMIC_INDEXING_PASSWORD")),
+            ..Default::default()
+        });
+        let report = cfg.lint();
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!(report.score(), 100);
+    }
+
+    #[test]
+    fn should_report_missing_anchor_once() {
+        let cfg = Config::new(&NetworkIdentifier::Mainnet, "test", None);
+        let anchor: Vec<_> = cfg
+            .lint()
+            .findings
+            .into_iter()
+            .filter(|f| f.diagnostic.path == "anchor")
+            .collect();
+        assert_eq!(anchor.len(), 1, "{:?}", anchor);
+        assert_eq!(anchor[0].rule, "anchor-required");
+    }
+
+    #[test]
+    fn should_not_hide_errors_behind_warnings() {
+        let cfg = Config::new(
+            &NetworkIdentifier::Mainnet,
+            "test",
+            Some(crate::CasAuth {
+                url: "https://cas.3boxlabs.com".to_string(),
+                pk: Some("not a seed".to_string()),
+            }),
+        );
+        let report = cfg.lint();
+        let seed: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.diagnostic.path == "anchor.private_seed_url")
+            .collect();
+        assert!(seed.iter().any(|f| f.rule == "secret-references"));
+        assert!(seed
+            .iter()
+            .any(|f| f.rule == "valid-config" && f.diagnostic.is_error()));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{Config, FileLogger, IndexingDb, Node};
+use serde_json::{Map, Value};
+
+/// Current version of the wheel config document
+pub const CONFIG_VERSION: u32 = 2;
+
+/// A migration applied while upgrading a config document
+#[derive(Clone, Debug)]
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub description: &'static str,
+}
+
+impl std::fmt::Display for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{} -> v{}: {}", self.from, self.to, self.description)
+    }
+}
+
+/// Config upgraded to [`CONFIG_VERSION`], along with the migrations applied to get there
+pub struct MigratedConfig {
+    pub config: Config,
+    pub applied: Vec<Migration>,
+}
+
+impl MigratedConfig {
+    pub fn was_migrated(&self) -> bool {
+        !self.applied.is_empty()
+    }
+
+    /// Version of the document before migration
+    pub fn original_version(&self) -> u32 {
+        self.applied
+            .first()
+            .map(|m| m.from)
+            .unwrap_or(self.config.version)
+    }
+}
+
+struct MigrationStep {
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>) -> anyhow::Result<()>,
+}
+
+/// Migrations indexed by the version they upgrade from
+const MIGRATIONS: &[MigrationStep] = &[
+    MigrationStep {
+        description:
+            "add version and fill node, anchor and logger settings missing from unversioned configs",
+        apply: migrate_v0,
+    },
+    MigrationStep {
+        description: "convert the indexing.db url to typed database settings, secrets such as the anchor private_seed_url may now be env: or file: references",
+        apply: migrate_v1,
+    },
+];
+
+fn insert_missing(obj: &mut Map<String, Value>, key: &str, value: Value) {
+    if !obj.contains_key(key) {
+        obj.insert(key.to_string(), value);
+    }
+}
+
+fn migrate_v0(doc: &mut Map<String, Value>) -> anyhow::Result<()> {
+    let node = Node::default();
+    match doc.get_mut("node") {
+        Some(Value::Object(n)) => {
+            insert_missing(n, "sync_override", Value::from(node.sync_override));
+            insert_missing(
+                n,
+                "stream_cache_limit",
+                Value::from(node.stream_cache_limit),
+            );
+        }
+        Some(_) => anyhow::bail!("node must be an object"),
+        None => {
+            doc.insert("node".to_string(), serde_json::to_value(node)?);
+        }
+    }
+    insert_missing(doc, "anchor", Value::from("None"));
+    if let Some(Value::Object(logger)) = doc.get_mut("logger") {
+        insert_missing(
+            logger,
+            "level",
+            serde_json::to_value(crate::LogLevel::default())?,
+        );
+        if let Some(Value::Object(file)) = logger.get_mut("file") {
+            insert_missing(file, "enabled", Value::from(FileLogger::default().enabled));
+        }
+    }
+    Ok(())
+}
+
+fn migrate_v1(doc: &mut Map<String, Value>) -> anyhow::Result<()> {
+    // the anchor seed only changed meaning, literal seeds are still read as literals
+    if let Some(Value::Object(indexing)) = doc.get_mut("indexing") {
+        if let Some(Value::String(db)) = indexing.get("db") {
+            let db = IndexingDb::from_url(db)?;
+            indexing.insert("db".to_string(), serde_json::to_value(db)?);
+        }
+    }
+    Ok(())
+}
+
+/// Upgrade a config document to [`CONFIG_VERSION`], applying each migration in turn
+pub fn migrate(value: Value) -> anyhow::Result<MigratedConfig> {
+    let (doc, applied) = migrate_value(value)?;
+    let config = serde_json::from_value(doc)?;
+    Ok(MigratedConfig { config, applied })
+}
+
+/// Upgrade a config document without requiring it to be a complete config
+pub fn migrate_value(value: Value) -> anyhow::Result<(Value, Vec<Migration>)> {
+    let mut doc = if let Value::Object(doc) = value {
+        doc
+    } else {
+        anyhow::bail!("Config must be a json object");
+    };
+    // editor schema references are not part of the config
+    doc.remove("$schema");
+    let mut version = match doc.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid config version {}", v))?,
+    };
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "Config version {} is newer than supported version {}, please upgrade wheel",
+            version,
+            CONFIG_VERSION
+        );
+    }
+    let mut applied = vec![];
+    while version < CONFIG_VERSION {
+        let step = &MIGRATIONS[version as usize];
+        (step.apply)(&mut doc)?;
+        let to = version + 1;
+        doc.insert("version".to_string(), Value::from(to));
+        log::debug!("Migrated config from v{} to v{}", version, to);
+        applied.push(Migration {
+            from: version,
+            to,
+            description: step.description,
+        });
+        version = to;
+    }
+    Ok((Value::Object(doc), applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_migrate_unversioned_config() {
+        let mut doc = serde_json::to_value(Config::default()).unwrap();
+        let obj = doc.as_object_mut().unwrap();
+        obj.remove("version");
+        obj.remove("anchor");
+        let node = obj.get_mut("node").unwrap().as_object_mut().unwrap();
+        node.remove("sync_override");
+        node.remove("stream_cache_limit");
+
+        let migrated = migrate(doc).unwrap();
+        assert!(migrated.was_migrated());
+        assert_eq!(migrated.original_version(), 0);
+        assert_eq!(migrated.config.version, CONFIG_VERSION);
+        assert_eq!(migrated.config.node.stream_cache_limit, 100);
+    }
+
+    #[test]
+    fn should_migrate_v1_indexing_url() {
+        let mut doc = serde_json::to_value(Config::default()).unwrap();
+        doc["version"] = Value::from(1);
+        doc["indexing"]["db"] = Value::from("postgres://ceramic:password@db:5432/ceramic");
+
+        let (value, applied) = migrate_value(doc).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].from, 1);
+        assert_eq!(value["indexing"]["db"]["Postgres"]["host"], "db");
+        let cfg: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            cfg.indexing.db.url().unwrap(),
+            "postgres://ceramic:password@db:5432/ceramic"
+        );
+    }
+
+    #[test]
+    fn should_not_migrate_current_config() {
+        let doc = serde_json::to_value(Config::default()).unwrap();
+        let migrated = migrate(doc).unwrap();
+        assert!(!migrated.was_migrated());
+    }
+
+    #[test]
+    fn should_reject_newer_config() {
+        let mut doc = serde_json::to_value(Config::default()).unwrap();
+        doc["version"] = Value::from(CONFIG_VERSION + 1);
+        assert!(migrate(doc).is_err());
+    }
+}
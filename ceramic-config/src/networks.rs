@@ -0,0 +1,261 @@
+use crate::{
+    convert_daemon_network_name, convert_network_identifier, ConfigFormat, NetworkIdentifier,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+/// Network file names searched for in a directory, in order of preference
+pub const NETWORKS_FILE_NAMES: &[&str] = &[
+    "networks.json",
+    "networks.toml",
+    "networks.yaml",
+    "networks.yml",
+];
+
+/// A network wheel can set up a node for. Built-in networks and user defined networks from a
+/// networks file share this definition.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct NetworkDefinition {
+    /// Name used to select the network, e.g. `clay` or `acme-private`
+    pub name: String,
+    /// Network name passed to the daemon, one of `inmemory`, `local`, `dev-unstable`,
+    /// `testnet-clay` or `mainnet`
+    #[serde(
+        serialize_with = "serialize_daemon_network",
+        deserialize_with = "deserialize_daemon_network"
+    )]
+    #[schemars(with = "String")]
+    pub daemon_network: NetworkIdentifier,
+    /// Pubsub topic, required by the daemon for `local` networks. Generated per project if not
+    /// set.
+    #[serde(default)]
+    pub pubsub_topic: Option<String>,
+    /// Default CAS url, networks without one do not anchor by default
+    #[serde(default)]
+    pub cas_url: Option<String>,
+    #[serde(default = "default_allows_sqlite")]
+    pub allows_sqlite: bool,
+    #[serde(skip)]
+    builtin: bool,
+}
+
+fn default_allows_sqlite() -> bool {
+    true
+}
+
+fn serialize_daemon_network<S: Serializer>(
+    id: &NetworkIdentifier,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(convert_network_identifier(id))
+}
+
+fn deserialize_daemon_network<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<NetworkIdentifier, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    convert_daemon_network_name(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown daemon network {}", name)))
+}
+
+impl NetworkDefinition {
+    fn builtin(
+        name: &str,
+        daemon_network: NetworkIdentifier,
+        cas_url: Option<&str>,
+        allows_sqlite: bool,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            daemon_network,
+            pubsub_topic: None,
+            cas_url: cas_url.map(str::to_string),
+            allows_sqlite,
+            builtin: true,
+        }
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        self.builtin
+    }
+}
+
+impl std::fmt::Display for NetworkDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.builtin {
+            write!(f, "{}", self.daemon_network)
+        } else {
+            write!(f, "{} ({})", self.name, self.daemon_network)
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct NetworksFile {
+    networks: Vec<NetworkDefinition>,
+}
+
+/// Built-in networks and any user defined networks
+#[derive(Clone, Debug)]
+pub struct NetworkRegistry {
+    networks: Vec<NetworkDefinition>,
+}
+
+impl Default for NetworkRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl NetworkRegistry {
+    pub fn builtin() -> Self {
+        Self {
+            networks: vec![
+                NetworkDefinition::builtin("in-memory", NetworkIdentifier::InMemory, None, true),
+                NetworkDefinition::builtin(
+                    "local",
+                    NetworkIdentifier::Local,
+                    Some("https://cas-qa.3boxlabs.com"),
+                    true,
+                ),
+                NetworkDefinition::builtin(
+                    "dev",
+                    NetworkIdentifier::Dev,
+                    Some("https://cas-qa.3boxlabs.com"),
+                    true,
+                ),
+                NetworkDefinition::builtin(
+                    "clay",
+                    NetworkIdentifier::Clay,
+                    Some("https://cas-clay.3boxlabs.com"),
+                    true,
+                ),
+                NetworkDefinition::builtin(
+                    "mainnet",
+                    NetworkIdentifier::Mainnet,
+                    Some("https://cas.3boxlabs.com"),
+                    false,
+                ),
+            ],
+        }
+    }
+
+    /// Built-in networks and the networks defined in a json, toml or yaml file of the form
+    /// `{"networks": [...]}`
+    pub fn load(file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let data = std::fs::read(file.as_ref()).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read networks file {}: {}",
+                file.as_ref().display(),
+                e
+            )
+        })?;
+        let value = ConfigFormat::from_path(file.as_ref()).parse(data.as_slice())?;
+        let parsed: NetworksFile = serde_json::from_value(value).map_err(|e| {
+            anyhow::anyhow!("Invalid networks file {}: {}", file.as_ref().display(), e)
+        })?;
+        let mut registry = Self::builtin();
+        for network in parsed.networks {
+            registry.add(network)?;
+        }
+        Ok(registry)
+    }
+
+    /// Load the networks file in a directory if there is one, otherwise only built-in networks
+    pub fn find(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match find_networks_file(dir) {
+            Some(file) => Self::load(file),
+            None => Ok(Self::builtin()),
+        }
+    }
+
+    pub fn add(&mut self, network: NetworkDefinition) -> anyhow::Result<()> {
+        if network.name.trim().is_empty() {
+            anyhow::bail!("Network name cannot be empty");
+        }
+        if self.get(&network.name).is_some() {
+            anyhow::bail!("Network {} is already defined", network.name);
+        }
+        self.networks.push(NetworkDefinition {
+            builtin: false,
+            ..network
+        });
+        Ok(())
+    }
+
+    /// Network by name, ignoring case
+    pub fn get(&self, name: &str) -> Option<&NetworkDefinition> {
+        self.networks
+            .iter()
+            .find(|n| n.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Built-in network for a daemon network
+    pub fn for_identifier(id: &NetworkIdentifier) -> NetworkDefinition {
+        Self::builtin()
+            .networks
+            .into_iter()
+            .find(|n| n.daemon_network == *id)
+            .expect("every network identifier has a built-in network")
+    }
+
+    pub fn networks(&self) -> &[NetworkDefinition] {
+        &self.networks
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.networks.iter().map(|n| n.name.as_str()).collect()
+    }
+}
+
+/// First networks file found in a directory
+pub fn find_networks_file(dir: impl AsRef<Path>) -> Option<PathBuf> {
+    NETWORKS_FILE_NAMES
+        .iter()
+        .map(|n| dir.as_ref().join(n))
+        .find(|p| p.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_load_custom_networks() {
+        let file = std::env::temp_dir().join("wheel-networks.toml");
+        std::fs::write(
+            &file,
+            r#"
+[[networks]]
+name = "acme-private"
+daemon_network = "local"
+pubsub_topic = "/ceramic/acme"
+cas_url = "http://cas.acme.internal:8081"
+allows_sqlite = false
+"#,
+        )
+        .unwrap();
+        let registry = NetworkRegistry::load(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let acme = registry.get("ACME-private").unwrap();
+        assert!(!acme.is_builtin());
+        assert_eq!(acme.daemon_network, NetworkIdentifier::Local);
+        assert_eq!(acme.pubsub_topic.as_deref(), Some("/ceramic/acme"));
+        assert!(!acme.allows_sqlite);
+        assert!(registry.get("clay").unwrap().is_builtin());
+        assert_eq!(registry.networks().len(), 6);
+    }
+
+    #[test]
+    fn should_reject_invalid_networks() {
+        let mut registry = NetworkRegistry::builtin();
+        let clay = registry.get("clay").unwrap().clone();
+        assert!(registry.add(clay).is_err());
+
+        let parsed: Result<NetworkDefinition, _> =
+            serde_json::from_str(r#"{"name": "acme", "daemon_network": "testnet-foo"}"#);
+        assert!(parsed.is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{Config, DaemonConfig};
+use serde_json::Value;
+
+/// File name of the wheel config schema, written next to the config
+pub const CONFIG_SCHEMA_FILE: &str = "ceramic.schema.json";
+/// File name of the daemon config schema
+pub const DAEMON_CONFIG_SCHEMA_FILE: &str = "daemon_config.schema.json";
+
+/// JSON Schema for the wheel config
+pub fn config_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default()
+}
+
+/// JSON Schema for the ceramic daemon config
+pub fn daemon_config_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(DaemonConfig)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_describe_enums() {
+        let schema = config_schema().to_string();
+        for name in [
+            "Bundled",
+            "Remote",
+            "RemoteDid",
+            "LocalDirectory",
+            "S3",
+            "Enabled",
+        ] {
+            assert!(schema.contains(name), "missing {}", name);
+        }
+        let schema = daemon_config_schema().to_string();
+        for name in ["anchor-service-url", "state-store", "bundled", "s3", "file"] {
+            assert!(schema.contains(name), "missing {}", name);
+        }
+    }
+}
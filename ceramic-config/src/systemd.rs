@@ -0,0 +1,202 @@
+use crate::{Config, IndexingDb, StateStore};
+use std::path::{Path, PathBuf};
+
+/// Name of the unit running the ceramic daemon
+pub const CERAMIC_UNIT_NAME: &str = "ceramic.service";
+/// Name of the unit running the demo application
+pub const APP_UNIT_NAME: &str = "ceramic-app.service";
+
+/// A systemd service unit for a wheel project
+#[derive(Clone, Debug)]
+pub struct SystemdUnit {
+    pub name: String,
+    pub description: String,
+    pub working_directory: PathBuf,
+    pub exec_start: String,
+    pub environment_file: Option<PathBuf>,
+    pub user: Option<String>,
+    pub after: Vec<String>,
+    pub requires: Vec<String>,
+    /// Paths the service may write to, everything else is read only
+    pub read_write_paths: Vec<PathBuf>,
+}
+
+fn absolute(working_directory: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        working_directory.join(path.strip_prefix(".").unwrap_or(path))
+    }
+}
+
+impl SystemdUnit {
+    /// Unit running the project's `ceramic` invoke script with its daemon config
+    pub fn ceramic(
+        cfg: &Config,
+        working_directory: &Path,
+        daemon_config_file: &Path,
+        environment_file: Option<&Path>,
+    ) -> Self {
+        let mut read_write_paths = vec![working_directory.to_path_buf()];
+        if let Some(file) = &cfg.logger.file {
+            if file.enabled {
+                read_write_paths.push(absolute(working_directory, &file.directory));
+            }
+        }
+        if let StateStore::LocalDirectory(dir) = &cfg.state_store {
+            read_write_paths.push(absolute(working_directory, dir));
+        }
+        let mut after = vec!["network-online.target".to_string()];
+        match &cfg.indexing.db {
+            IndexingDb::Sqlite(db) => {
+                if let Some(dir) = db.parent() {
+                    read_write_paths.push(absolute(working_directory, dir));
+                }
+            }
+            IndexingDb::Postgres(pg) if pg.host == "localhost" || pg.host == "127.0.0.1" => {
+                after.push("postgresql.service".to_string());
+            }
+            _ => {}
+        }
+        read_write_paths.sort();
+        read_write_paths.dedup();
+        Self {
+            name: CERAMIC_UNIT_NAME.to_string(),
+            description: format!("Ceramic node on {}", cfg.network),
+            working_directory: working_directory.to_path_buf(),
+            exec_start: format!(
+                "/bin/sh {} daemon --config {}",
+                working_directory.join("ceramic").display(),
+                daemon_config_file.display()
+            ),
+            environment_file: environment_file.map(Path::to_path_buf),
+            user: None,
+            after,
+            requires: vec![],
+            read_write_paths,
+        }
+    }
+
+    /// Unit running the demo application against the ceramic unit
+    pub fn demo_app(app_directory: &Path) -> Self {
+        Self {
+            name: APP_UNIT_NAME.to_string(),
+            description: "Ceramic demo application".to_string(),
+            working_directory: app_directory.to_path_buf(),
+            exec_start: "/usr/bin/env npm run nextDev".to_string(),
+            environment_file: None,
+            user: None,
+            after: vec![CERAMIC_UNIT_NAME.to_string()],
+            requires: vec![CERAMIC_UNIT_NAME.to_string()],
+            read_write_paths: vec![app_directory.to_path_buf()],
+        }
+    }
+
+    pub fn with_user(mut self, user: Option<String>) -> Self {
+        self.user = user;
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut unit = vec![
+            "[Unit]".to_string(),
+            format!("Description={}", self.description),
+        ];
+        if !self.after.is_empty() {
+            unit.push(format!("After={}", self.after.join(" ")));
+            unit.push(format!("Wants={}", self.after.join(" ")));
+        }
+        if !self.requires.is_empty() {
+            unit.push(format!("Requires={}", self.requires.join(" ")));
+        }
+
+        unit.push(String::default());
+        unit.push("[Service]".to_string());
+        unit.push("Type=simple".to_string());
+        if let Some(user) = &self.user {
+            unit.push(format!("User={}", user));
+        }
+        unit.push(format!(
+            "WorkingDirectory={}",
+            self.working_directory.display()
+        ));
+        if let Some(env) = &self.environment_file {
+            // the leading dash allows the file to be missing
+            unit.push(format!("EnvironmentFile=-{}", env.display()));
+        }
+        unit.push(format!("ExecStart={}", self.exec_start));
+        unit.push("Restart=on-failure".to_string());
+        unit.push("RestartSec=5".to_string());
+        unit.push("NoNewPrivileges=true".to_string());
+        unit.push("PrivateTmp=true".to_string());
+        unit.push("ProtectSystem=strict".to_string());
+        unit.push("ProtectHome=read-only".to_string());
+        unit.push("ProtectKernelTunables=true".to_string());
+        unit.push("ProtectKernelModules=true".to_string());
+        unit.push("ProtectControlGroups=true".to_string());
+        unit.push("RestrictSUIDSGID=true".to_string());
+        unit.push("LockPersonality=true".to_string());
+        for path in &self.read_write_paths {
+            // the leading dash keeps a missing directory from failing the unit
+            unit.push(format!("ReadWritePaths=-{}", path.display()));
+        }
+
+        unit.push(String::default());
+        unit.push("[Install]".to_string());
+        unit.push("WantedBy=multi-user.target".to_string());
+        unit.push(String::default());
+        unit.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_render_ceramic_unit() {
+        let mut cfg = Config::new(&crate::NetworkIdentifier::Clay, "test", None);
+        cfg.indexing.db = IndexingDb::Sqlite("/var/lib/ceramic/indexing/ceramic.db".into());
+        let wd = Path::new("/srv/ceramic-app");
+        let unit = SystemdUnit::ceramic(
+            &cfg,
+            wd,
+            &wd.join("daemon_config.json"),
+            Some(&wd.join("ceramic.env")),
+        )
+        .with_user(Some("ceramic".to_string()));
+        assert_eq!(
+            unit.render(),
+            r#"[Unit]
+Description=Ceramic node on Clay
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+User=ceramic
+WorkingDirectory=/srv/ceramic-app
+EnvironmentFile=-/srv/ceramic-app/ceramic.env
+ExecStart=/bin/sh /srv/ceramic-app/ceramic daemon --config /srv/ceramic-app/daemon_config.json
+Restart=on-failure
+RestartSec=5
+NoNewPrivileges=true
+PrivateTmp=true
+ProtectSystem=strict
+ProtectHome=read-only
+ProtectKernelTunables=true
+ProtectKernelModules=true
+ProtectControlGroups=true
+RestrictSUIDSGID=true
+LockPersonality=true
+ReadWritePaths=-/etc/ceramic/data
+ReadWritePaths=-/srv/ceramic-app
+ReadWritePaths=-/srv/ceramic-app/log/ceramic
+ReadWritePaths=-/var/lib/ceramic/indexing
+
+[Install]
+WantedBy=multi-user.target
+"#
+        );
+    }
+}
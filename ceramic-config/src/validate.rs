@@ -0,0 +1,390 @@
+use crate::{
+    redact, Anchor, Config, IndexingDb, Ipfs, Metrics, NetworkIdentifier, Secret, SecretSource,
+    StateStore,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found with a config value, identified by its dotted field path
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub path: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+fn validate_url(path: &str, value: &str, schemes: &[&str], out: &mut Vec<Diagnostic>) {
+    match url::Url::parse(value) {
+        Err(e) => out.push(Diagnostic::error(
+            path,
+            format!("'{}' is not a valid url: {}", value, e),
+        )),
+        Ok(u) => {
+            let value = if u.password().is_some() {
+                redact(value)
+            } else {
+                value.to_string()
+            };
+            if !schemes.contains(&u.scheme()) {
+                out.push(Diagnostic::error(
+                    path,
+                    format!(
+                        "'{}' must use one of the schemes {}",
+                        value,
+                        schemes.join(", ")
+                    ),
+                ));
+            } else if u.host_str().map(str::is_empty).unwrap_or(true) {
+                out.push(Diagnostic::error(
+                    path,
+                    format!("'{}' does not specify a host", value),
+                ));
+            }
+        }
+    }
+}
+
+/// Check a DID has the form `did:<method>:<method-specific-id>`
+fn is_valid_did(did: &str) -> bool {
+    let mut parts = did.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("did"), Some(method), Some(id)) => {
+            !method.is_empty()
+                && method
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && !id.is_empty()
+        }
+        _ => false,
+    }
+}
+
+fn validate_indexing(cfg: &Config, out: &mut Vec<Diagnostic>) {
+    match &cfg.indexing.db {
+        IndexingDb::Sqlite(path) => {
+            if !cfg.allows_sqlite() {
+                out.push(Diagnostic::error(
+                    "indexing.db",
+                    format!("sqlite is not allowed on network {}", cfg.network),
+                ));
+            }
+            if path.as_os_str().is_empty() {
+                out.push(Diagnostic::error(
+                    "indexing.db.Sqlite",
+                    "a path to the database file is required",
+                ));
+            }
+        }
+        IndexingDb::Postgres(pg) => {
+            for (field, value) in [
+                ("host", &pg.host),
+                ("user", &pg.user),
+                ("database", &pg.database),
+            ] {
+                if value.trim().is_empty() {
+                    out.push(Diagnostic::error(
+                        format!("indexing.db.Postgres.{}", field),
+                        format!("{} is required", field),
+                    ));
+                }
+            }
+            if pg.port == 0 {
+                out.push(Diagnostic::error(
+                    "indexing.db.Postgres.port",
+                    "port must be greater than 0",
+                ));
+            }
+            if pg.pool_size == Some(0) {
+                out.push(Diagnostic::error(
+                    "indexing.db.Postgres.pool_size",
+                    "pool size must be greater than 0",
+                ));
+            }
+        }
+        // references are only resolved when connecting
+        IndexingDb::Url(_) => {}
+    }
+}
+
+fn validate_private_seed_url(private_seed_url: &Secret, out: &mut Vec<Diagnostic>) {
+    let SecretSource::Literal(private_seed_url) = private_seed_url.source() else {
+        return;
+    };
+    if !private_seed_url.starts_with("inplace:ed25519#")
+        || private_seed_url.len() == "inplace:ed25519#".len()
+    {
+        out.push(Diagnostic::error(
+            "anchor.private_seed_url",
+            "must have the form inplace:ed25519#<hex encoded private key>",
+        ));
+    }
+}
+
+fn validate_anchor(cfg: &Config, out: &mut Vec<Diagnostic>) {
+    let id = &cfg.network.id;
+    let url = match &cfg.anchor {
+        Anchor::None => {
+            match id {
+                NetworkIdentifier::InMemory => {}
+                NetworkIdentifier::Local | NetworkIdentifier::Dev => {
+                    out.push(Diagnostic::warning(
+                        "anchor",
+                        format!(
+                            "no anchor service configured, streams on {} will not be anchored",
+                            id
+                        ),
+                    ));
+                }
+                NetworkIdentifier::Clay | NetworkIdentifier::Mainnet => {
+                    out.push(Diagnostic::error(
+                        "anchor",
+                        format!("an anchor service is required on {}", id),
+                    ));
+                }
+            }
+            return;
+        }
+        Anchor::Ip { url } => url,
+        Anchor::RemoteDid {
+            url,
+            private_seed_url,
+        } => {
+            validate_private_seed_url(private_seed_url, out);
+            url
+        }
+        Anchor::SelfHosted {
+            url,
+            auth_method,
+            private_seed_url,
+        } => {
+            validate_url("anchor.url", url, &["http", "https"], out);
+            match (auth_method.as_deref(), private_seed_url) {
+                (Some("did"), None) => out.push(Diagnostic::error(
+                    "anchor.private_seed_url",
+                    "required for did authentication",
+                )),
+                (_, Some(private_seed_url)) => validate_private_seed_url(private_seed_url, out),
+                _ => {}
+            }
+            if *id == NetworkIdentifier::InMemory {
+                out.push(Diagnostic::warning(
+                    "anchor",
+                    format!("{} does not anchor, anchor service will not be used", id),
+                ));
+            }
+            return;
+        }
+    };
+    validate_url("anchor.url", url, &["http", "https"], out);
+    if *id == NetworkIdentifier::InMemory {
+        out.push(Diagnostic::warning(
+            "anchor",
+            format!("{} does not anchor, anchor service will not be used", id),
+        ));
+    } else if let Some(expected) = cfg.network.cas_url() {
+        if url.trim_end_matches('/') != expected.trim_end_matches('/') {
+            out.push(Diagnostic::warning(
+                "anchor.url",
+                format!(
+                    "'{}' is not the default anchor service {} for {}",
+                    url, expected, cfg.network
+                ),
+            ));
+        }
+    }
+}
+
+impl Config {
+    /// Check the config for values the ceramic daemon will reject or misbehave with
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut out = vec![];
+
+        if let Ipfs::Remote(r) = &self.ipfs {
+            validate_url("ipfs.host", &r.host, &["http", "https"], &mut out);
+            for (i, endpoint) in r.pinning_endpoints.iter().enumerate() {
+                if endpoint.trim().is_empty() {
+                    out.push(Diagnostic::error(
+                        format!("ipfs.pinning_endpoints.{}", i),
+                        "pinning endpoint is empty",
+                    ));
+                }
+            }
+            if r.request_timeout_ms == Some(0) {
+                out.push(Diagnostic::error(
+                    "ipfs.request_timeout_ms",
+                    "request timeout must be greater than 0",
+                ));
+            }
+        }
+
+        match &self.state_store {
+            StateStore::S3(s3) => {
+                if s3.bucket.trim().is_empty() {
+                    out.push(Diagnostic::error(
+                        "state_store.bucket",
+                        "bucket is required",
+                    ));
+                }
+                if !s3.endpoint.is_empty() {
+                    validate_url(
+                        "state_store.endpoint",
+                        &s3.endpoint,
+                        &["http", "https"],
+                        &mut out,
+                    );
+                }
+            }
+            StateStore::LocalDirectory(p) => {
+                if p.as_os_str().is_empty() {
+                    out.push(Diagnostic::error("state_store", "directory is required"));
+                }
+            }
+        }
+
+        if self.http_api.hostname.trim().is_empty() {
+            out.push(Diagnostic::error(
+                "http_api.hostname",
+                "hostname is required",
+            ));
+        }
+        for (i, origin) in self.http_api.cors_allowed_origins.iter().enumerate() {
+            if origin.trim().is_empty() {
+                out.push(Diagnostic::error(
+                    format!("http_api.cors_allowed_origins.{}", i),
+                    "origin must not be empty",
+                ));
+            }
+        }
+        for (i, did) in self.http_api.admin_dids.iter().enumerate() {
+            if !is_valid_did(did) {
+                out.push(Diagnostic::error(
+                    format!("http_api.admin_dids.{}", i),
+                    format!("'{}' is not a valid did", did),
+                ));
+            }
+        }
+
+        if self.network.id == NetworkIdentifier::Local && self.network.pubsub_topic.is_none() {
+            out.push(Diagnostic::error(
+                "network.pubsub_topic",
+                "a pubsub topic is required for local networks",
+            ));
+        }
+
+        validate_anchor(self, &mut out);
+        if let Some(rpc) = &self.ethereum_rpc_url {
+            validate_url("ethereum_rpc_url", rpc, &["http", "https"], &mut out);
+        }
+        validate_indexing(self, &mut out);
+
+        for (i, network) in self.did_resolvers.ethr_networks().iter().enumerate() {
+            if network.name.trim().is_empty() {
+                out.push(Diagnostic::error(
+                    format!("did_resolvers.Ethr.networks.{}.name", i),
+                    "network name is required",
+                ));
+            }
+            validate_url(
+                &format!("did_resolvers.Ethr.networks.{}.rpcUrl", i),
+                &network.rpc_url,
+                &["http", "https", "ws", "wss"],
+                &mut out,
+            );
+        }
+
+        if let Metrics::Enabled(host) = &self.metrics {
+            validate_url("metrics", host, &["http", "https"], &mut out);
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_validate_default_config() {
+        let cfg = Config::new(
+            &NetworkIdentifier::Clay,
+            "test",
+            Some(crate::CasAuth {
+                url: "https://cas-clay.3boxlabs.com".to_string(),
+                pk: Some("inplace:ed25519#00".to_string()),
+            }),
+        );
+        let diagnostics = cfg.validate();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn should_report_invalid_values() {
+        let mut cfg = Config::new(&NetworkIdentifier::Mainnet, "test", None);
+        cfg.ipfs = Ipfs::Remote(crate::IpfsRemote {
+            host: "localhost".to_string(),
+            ..Default::default()
+        });
+        cfg.http_api.cors_allowed_origins = vec!["".to_string()];
+        cfg.http_api.admin_dids = vec!["did:key".to_string()];
+        cfg.indexing.db = IndexingDb::Sqlite("/tmp/ceramic.db".into());
+
+        let paths: Vec<_> = cfg
+            .validate()
+            .into_iter()
+            .filter(Diagnostic::is_error)
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "ipfs.host",
+                "http_api.cors_allowed_origins.0",
+                "http_api.admin_dids.0",
+                "anchor",
+                "indexing.db",
+            ]
+        );
+    }
+}
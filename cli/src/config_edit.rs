@@ -0,0 +1,101 @@
+use ceramic_config::{Config, ConfigLayers};
+use inquire::{Confirm, InquireError};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Copy of the config being edited, kept next to it so relative schema references still resolve
+fn edit_file(cfg_file_path: &Path) -> PathBuf {
+    let stem = cfg_file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match cfg_file_path.extension() {
+        Some(e) => format!(".{}.edit.{}", stem, e.to_string_lossy()),
+        None => format!(".{}.edit", stem),
+    };
+    cfg_file_path.with_file_name(name)
+}
+
+async fn run_editor(file: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+    // run through the shell so editors configured with arguments, e.g. `code --wait`, work
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(file)
+        .status()
+        .await?;
+    if !status.success() {
+        anyhow::bail!("Editor {} exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Parse an edited config the same way as the project config, over defaults
+fn parse(file: &Path) -> anyhow::Result<Config> {
+    let cfg = ConfigLayers::default().load_file(file)?.config;
+    let diagnostics = cfg.validate();
+    let errors: Vec<_> = diagnostics.iter().filter(|d| d.is_error()).collect();
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "Config is invalid\n{}",
+            errors
+                .iter()
+                .map(|d| format!("    {}", d))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(cfg)
+}
+
+/// Edit a copy of the config in the user's editor until it parses and validates or the user gives
+/// up. Returns None if the edit was abandoned.
+pub async fn edit(cfg_file_path: &Path) -> anyhow::Result<Option<Config>> {
+    let file = edit_file(cfg_file_path);
+    tokio::fs::copy(cfg_file_path, &file).await?;
+    let res = loop {
+        if let Err(e) = run_editor(&file).await {
+            break Err(e);
+        }
+        match parse(&file) {
+            Ok(cfg) => break Ok(Some(cfg)),
+            Err(e) => {
+                log::error!("{}", e);
+                let again = Confirm::new("Edit the config again?")
+                    .with_help_message("Otherwise your changes are discarded")
+                    .with_default(true)
+                    .prompt();
+                match again {
+                    Ok(true) => {}
+                    Ok(false) => break Ok(None),
+                    Err(e) => break Err(e.into()),
+                }
+            }
+        }
+    };
+    // the copy is removed even when editing failed, so it is not mistaken for a config
+    tokio::fs::remove_file(&file).await?;
+    res
+}
+
+/// Whether a running daemon should be restarted after its config changed, asking if not already
+/// decided
+pub fn should_restart(restart: Option<bool>) -> anyhow::Result<bool> {
+    if let Some(restart) = restart {
+        return Ok(restart);
+    }
+    match Confirm::new("Ceramic is running, restart it to apply the change?")
+        .with_default(true)
+        .prompt()
+    {
+        Ok(restart) => Ok(restart),
+        Err(InquireError::NotTTY) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
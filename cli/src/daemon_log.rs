@@ -0,0 +1,375 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Child;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// File the daemon's output is written to, rotated to `ceramic.log.1` and so on
+pub const LOG_FILE: &str = "ceramic.log";
+
+const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
+/// Rotated files kept in addition to the current file
+const MAX_ROTATED_FILES: usize = 5;
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Which daemon output is echoed to wheel's own output, all of it is always written to the log
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DaemonOutput {
+    None,
+    #[default]
+    Errors,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+    /// Events from wheel about the daemon, such as restarts
+    Wheel,
+}
+
+impl std::fmt::Display for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdout => write!(f, "stdout"),
+            Self::Stderr => write!(f, "stderr"),
+            Self::Wheel => write!(f, "wheel"),
+        }
+    }
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), n))
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+    /// Size the file is rotated at
+    max_size: u64,
+}
+
+impl RotatingFile {
+    async fn open(path: PathBuf, max_size: u64) -> anyhow::Result<Self> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        let size = file.metadata().await?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+        })
+    }
+
+    async fn rotate(&mut self) -> anyhow::Result<()> {
+        self.file.flush().await?;
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated(&self.path, n);
+            if tokio::fs::try_exists(&from).await? {
+                tokio::fs::rename(&from, rotated(&self.path, n + 1)).await?;
+            }
+        }
+        tokio::fs::rename(&self.path, rotated(&self.path, 1)).await?;
+        *self = Self::open(self.path.clone(), self.max_size).await?;
+        Ok(())
+    }
+
+    async fn write_line(&mut self, line: &str) -> anyhow::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate().await?;
+        }
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.write_all(b"\n").await?;
+        self.file.flush().await?;
+        self.size += len;
+        Ok(())
+    }
+}
+
+/// Timestamped, size rotated log of the daemon's stdout and stderr
+#[derive(Clone)]
+pub struct DaemonLog {
+    path: PathBuf,
+    file: Arc<Mutex<RotatingFile>>,
+    output: DaemonOutput,
+}
+
+impl DaemonLog {
+    pub async fn open(working_directory: &Path, output: DaemonOutput) -> anyhow::Result<Self> {
+        let path = working_directory.join(LOG_FILE);
+        let file = RotatingFile::open(path.clone(), MAX_LOG_SIZE).await?;
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+            output,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn write(&self, stream: Stream, line: &str) {
+        match (self.output, stream) {
+            (DaemonOutput::All, Stream::Stdout) => log::info!("[ceramic] {}", line),
+            (DaemonOutput::All | DaemonOutput::Errors, Stream::Stderr) => {
+                log::warn!("[ceramic] {}", line)
+            }
+            _ => {}
+        }
+        let line = format!(
+            "{} [{}] {}",
+            humantime::format_rfc3339_millis(SystemTime::now()),
+            stream,
+            line
+        );
+        if let Err(e) = self.file.lock().await.write_line(&line).await {
+            log::warn!("Failed to write to {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Drain the child's piped stdout and stderr into the log until both close
+    pub fn capture(&self, child: &mut Child) -> JoinHandle<()> {
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let log = self.clone();
+        tokio::spawn(async move {
+            let out = async {
+                if let Some(stdout) = stdout {
+                    log.drain(Stream::Stdout, stdout).await;
+                }
+            };
+            let err = async {
+                if let Some(stderr) = stderr {
+                    log.drain(Stream::Stderr, stderr).await;
+                }
+            };
+            tokio::join!(out, err);
+        })
+    }
+
+    async fn drain(&self, stream: Stream, reader: impl AsyncRead + Unpin) {
+        let mut reader = BufReader::new(reader);
+        let mut buf = vec![];
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf);
+                    self.write(stream, line.trim_end()).await;
+                }
+                Err(e) => {
+                    log::warn!("Failed to read ceramic {}: {}", stream, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Which log lines `wheel logs` prints
+#[derive(Default)]
+pub struct LogFilter {
+    pub grep: Option<regex::Regex>,
+    pub since: Option<SystemTime>,
+}
+
+impl LogFilter {
+    /// `since` is either a duration before now, e.g. `10m` or `1h 30m`, or an RFC 3339 timestamp
+    pub fn new(grep: Option<&str>, since: Option<&str>) -> anyhow::Result<Self> {
+        let grep = grep.map(regex::Regex::new).transpose()?;
+        let since = if let Some(since) = since {
+            let time = match humantime::parse_duration(since) {
+                Ok(d) => SystemTime::now() - d,
+                Err(_) => humantime::parse_rfc3339_weak(since).map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid --since {}, expected a duration like 10m or a timestamp like 2024-01-01T12:00:00",
+                        since
+                    )
+                })?,
+            };
+            Some(time)
+        } else {
+            None
+        };
+        Ok(Self { grep, since })
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        if let Some(since) = self.since {
+            let time = line
+                .split_once(' ')
+                .and_then(|(ts, _)| humantime::parse_rfc3339(ts).ok());
+            match time {
+                Some(time) if time >= since => {}
+                _ => return false,
+            }
+        }
+        self.grep.as_ref().map(|r| r.is_match(line)).unwrap_or(true)
+    }
+}
+
+fn print_all_lines(filter: &LogFilter, buf: &mut Vec<u8>) {
+    if !buf.is_empty() && !buf.ends_with(b"\n") {
+        buf.push(b'\n');
+    }
+    print_lines(filter, buf);
+}
+
+fn print_lines(filter: &LogFilter, buf: &mut Vec<u8>) {
+    // keep a trailing partial line until the rest is written
+    let end = buf
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    for line in String::from_utf8_lossy(&buf[..end]).lines() {
+        if filter.matches(line) {
+            println!("{}", line);
+        }
+    }
+    buf.drain(..end);
+}
+
+/// Print the daemon log, oldest first, optionally following new lines across rotations
+pub async fn print_log(
+    working_directory: &Path,
+    filter: &LogFilter,
+    follow: bool,
+) -> anyhow::Result<()> {
+    let path = working_directory.join(LOG_FILE);
+    for n in (1..=MAX_ROTATED_FILES).rev() {
+        let file = rotated(&path, n);
+        if tokio::fs::try_exists(&file).await? {
+            let mut buf = tokio::fs::read(&file).await?;
+            print_all_lines(filter, &mut buf);
+        }
+    }
+    if !tokio::fs::try_exists(&path).await? {
+        if follow {
+            log::info!("Waiting for {} to be created", path.display());
+        } else {
+            log::info!("No daemon log at {}", path.display());
+            return Ok(());
+        }
+    }
+    let mut file = None;
+    let mut buf = vec![];
+    loop {
+        if file.is_none() && tokio::fs::try_exists(&path).await? {
+            file = Some(tokio::fs::File::open(&path).await?);
+        }
+        if let Some(f) = file.as_mut() {
+            f.read_to_end(&mut buf).await?;
+            if !follow {
+                print_all_lines(filter, &mut buf);
+                return Ok(());
+            }
+            print_lines(filter, &mut buf);
+            // after rotation the open file is the rotated one, so finish reading it and switch to
+            // the new file
+            let rotated = match tokio::fs::metadata(&path).await {
+                Ok(m) => m.ino() != f.metadata().await?.ino(),
+                Err(_) => true,
+            };
+            if rotated {
+                f.read_to_end(&mut buf).await?;
+                print_lines(filter, &mut buf);
+                file = None;
+                buf.clear();
+            }
+        }
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wheel-{}-{}", name, std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        dir
+    }
+
+    async fn read(path: &Path) -> Option<String> {
+        tokio::fs::read_to_string(path).await.ok()
+    }
+
+    #[tokio::test]
+    async fn should_rotate_newest_first_and_drop_oldest() {
+        let dir = test_dir("rotate").await;
+        let path = dir.join(LOG_FILE);
+        let mut file = RotatingFile::open(path.clone(), 10).await.unwrap();
+        for n in 0..7 {
+            file.write_line(&format!("line{}", n)).await.unwrap();
+        }
+        assert_eq!(read(&path).await.unwrap(), "line6\n");
+        for n in 1..=MAX_ROTATED_FILES {
+            assert_eq!(
+                read(&rotated(&path, n)).await.unwrap(),
+                format!("line{}\n", 6 - n)
+            );
+        }
+        assert!(read(&rotated(&path, MAX_ROTATED_FILES + 1)).await.is_none());
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_rotate_once_size_is_exceeded() {
+        let dir = test_dir("size").await;
+        let path = dir.join(LOG_FILE);
+        let mut file = RotatingFile::open(path.clone(), 20).await.unwrap();
+        for _ in 0..4 {
+            file.write_line("abcd").await.unwrap();
+        }
+        assert!(read(&rotated(&path, 1)).await.is_none());
+        file.write_line("abcd").await.unwrap();
+        assert_eq!(read(&rotated(&path, 1)).await.unwrap(), "abcd\n".repeat(4));
+        assert_eq!(read(&path).await.unwrap(), "abcd\n");
+
+        // the size of an existing file counts when it is reopened
+        let mut file = RotatingFile::open(path.clone(), 9).await.unwrap();
+        file.write_line("abcd").await.unwrap();
+        assert_eq!(read(&rotated(&path, 1)).await.unwrap(), "abcd\n");
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    fn line(age: Duration, message: &str) -> String {
+        format!(
+            "{} [stdout] {}",
+            humantime::format_rfc3339_millis(SystemTime::now() - age),
+            message
+        )
+    }
+
+    #[test]
+    fn should_filter_by_since_and_grep() {
+        let filter = LogFilter::new(Some("error"), Some("10m")).unwrap();
+        assert!(filter.matches(&line(Duration::from_secs(60), "an error")));
+        assert!(!filter.matches(&line(Duration::from_secs(3600), "an error")));
+        assert!(!filter.matches(&line(Duration::from_secs(60), "all good")));
+        assert!(!filter.matches("an error without a timestamp"));
+
+        let filter = LogFilter::new(None, Some("2024-01-01T12:00:00")).unwrap();
+        assert!(filter.matches("2024-01-01T12:00:00.001Z [wheel] restarting"));
+        assert!(!filter.matches("2024-01-01T11:59:59.999Z [wheel] restarting"));
+
+        let filter = LogFilter::new(Some("error"), None).unwrap();
+        assert!(filter.matches("an error without a timestamp"));
+        assert!(LogFilter::new(None, Some("yesterday")).is_err());
+    }
+}
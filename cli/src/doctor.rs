@@ -0,0 +1,460 @@
+use crate::install::verify_db;
+use crate::lifecycle;
+use crate::readiness::{check_ipfs, CheckResult};
+use ceramic_config::{
+    find_networks_file, Config, ConfigLayers, DaemonConfig, IndexingDb, NetworkRegistry, StateStore,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Oldest node release supported by the ceramic cli
+const MIN_NODE_VERSION: semver::Version = semver::Version::new(20, 8, 0);
+const MIN_NPM_VERSION: semver::Version = semver::Version::new(10, 0, 0);
+const NODE_DOWNLOAD_URL: &str = "https://nodejs.org/en/download";
+const DAEMON_CONFIG_FILE: &str = "daemon_config.json";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+    Skipped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// What to do about a failed check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(name: &str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn ok(name: &str, message: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Ok, message)
+    }
+
+    fn warning(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warning, message).with_fix(fix)
+    }
+
+    fn error(name: &str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Error, message).with_fix(fix)
+    }
+
+    fn skipped(name: &str, message: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Skipped, message)
+    }
+
+    fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Results of `wheel doctor`
+#[derive(Clone, Debug, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn errors(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Error)
+            .count()
+    }
+}
+
+impl std::fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Ok => "ok",
+                CheckStatus::Warning => "warn",
+                CheckStatus::Error => "error",
+                CheckStatus::Skipped => "skip",
+            };
+            writeln!(f, "[{:5}] {}: {}", status, check.name, check.message)?;
+            if let Some(fix) = &check.fix {
+                writeln!(f, "        fix: {}", fix)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn tool_version(tool: &str) -> anyhow::Result<semver::Version> {
+    let out = Command::new(tool).arg("--version").output().await?;
+    if !out.status.success() {
+        anyhow::bail!("{} --version failed", tool);
+    }
+    let version = String::from_utf8_lossy(&out.stdout);
+    Ok(version.trim().trim_start_matches('v').parse()?)
+}
+
+async fn check_tools() -> Vec<DoctorCheck> {
+    let mut checks = vec![];
+    for tool in ["sh", "node", "npm"] {
+        if let Err(e) = which::which(tool) {
+            let fix = if tool == "sh" {
+                "Install a posix shell and add it to PATH".to_string()
+            } else {
+                format!("Install Node.js from {}", NODE_DOWNLOAD_URL)
+            };
+            checks.push(DoctorCheck::error(
+                tool,
+                format!("{} not found: {}", tool, e),
+                fix,
+            ));
+            continue;
+        }
+        let min = match tool {
+            "node" => MIN_NODE_VERSION,
+            "npm" => MIN_NPM_VERSION,
+            _ => {
+                checks.push(DoctorCheck::ok(tool, "found"));
+                continue;
+            }
+        };
+        let check = match tool_version(tool).await {
+            Ok(v) if v >= min => DoctorCheck::ok(tool, format!("version {}", v)),
+            Ok(v) if tool == "node" => DoctorCheck::error(
+                tool,
+                format!("version {} is older than {} required by ceramic", v, min),
+                format!(
+                    "Install Node.js {} or later from {}",
+                    min, NODE_DOWNLOAD_URL
+                ),
+            ),
+            Ok(v) => DoctorCheck::warning(
+                tool,
+                format!("version {} is older than {}", v, min),
+                format!("Update npm with `npm install -g npm@{}`", min.major),
+            ),
+            Err(e) => DoctorCheck::warning(
+                tool,
+                format!("could not determine version: {}", e),
+                format!("Check that `{} --version` works", tool),
+            ),
+        };
+        checks.push(check);
+    }
+    checks
+}
+
+async fn check_port(working_directory: &Path, cfg: &Config) -> DoctorCheck {
+    let name = "http port";
+    let addr = (cfg.http_api.hostname.as_str(), cfg.http_api.port);
+    if let Ok(Some(daemon)) = lifecycle::running(working_directory).await {
+        return DoctorCheck::ok(
+            name,
+            format!("port {} is used by ceramic with pid {}", addr.1, daemon.pid),
+        );
+    }
+    match tokio::net::TcpListener::bind(addr).await {
+        Ok(_) => DoctorCheck::ok(name, format!("{}:{} is free", addr.0, addr.1)),
+        Err(e) => DoctorCheck::error(
+            name,
+            format!("cannot listen on {}:{}: {}", addr.0, addr.1, e),
+            format!(
+                "Stop the process using port {} or change it with `wheel --set http_api.port=<port>`",
+                addr.1
+            ),
+        ),
+    }
+}
+
+fn resolve(working_directory: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        working_directory.join(path.strip_prefix(".").unwrap_or(path))
+    }
+}
+
+/// Check a directory can be written, or created if it does not exist yet
+async fn check_writable(name: &str, dir: &Path) -> DoctorCheck {
+    let fix = format!(
+        "Create {} and make it writable by the user running ceramic",
+        dir.display()
+    );
+    if !dir.exists() {
+        let ancestor = dir.ancestors().find(|p| p.exists());
+        return match ancestor {
+            Some(a) if is_writable(a).await => DoctorCheck::ok(
+                name,
+                format!("{} does not exist and will be created", dir.display()),
+            ),
+            _ => DoctorCheck::error(
+                name,
+                format!("{} does not exist and cannot be created", dir.display()),
+                fix,
+            ),
+        };
+    }
+    if !dir.is_dir() {
+        return DoctorCheck::error(name, format!("{} is not a directory", dir.display()), fix);
+    }
+    if is_writable(dir).await {
+        DoctorCheck::ok(name, format!("{} is writable", dir.display()))
+    } else {
+        DoctorCheck::error(name, format!("{} is not writable", dir.display()), fix)
+    }
+}
+
+async fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".wheel-doctor-{}", std::process::id()));
+    let writable = tokio::fs::write(&probe, b"").await.is_ok();
+    let _ = tokio::fs::remove_file(&probe).await;
+    writable
+}
+
+async fn check_directories(working_directory: &Path, cfg: &Config) -> Vec<DoctorCheck> {
+    let mut checks = vec![];
+    match &cfg.state_store {
+        StateStore::LocalDirectory(dir) => {
+            checks.push(check_writable("state store", &resolve(working_directory, dir)).await)
+        }
+        StateStore::S3(_) => {
+            checks.push(DoctorCheck::skipped("state store", "state is stored in s3"))
+        }
+    }
+    match &cfg.logger.file {
+        Some(file) if file.enabled => checks.push(
+            check_writable(
+                "log directory",
+                &resolve(working_directory, &file.directory),
+            )
+            .await,
+        ),
+        _ => checks.push(DoctorCheck::skipped(
+            "log directory",
+            "file logging is disabled",
+        )),
+    }
+    checks
+}
+
+async fn check_sqlite(working_directory: &Path, cfg: &Config) -> DoctorCheck {
+    let name = "sqlite";
+    let path = match &cfg.indexing.db {
+        IndexingDb::Sqlite(path) => resolve(working_directory, path),
+        _ => return DoctorCheck::skipped(name, "indexing uses postgres"),
+    };
+    let dir = path.parent().unwrap_or(working_directory);
+    if !path.exists() {
+        let mut check = check_writable(name, dir).await;
+        if check.status == CheckStatus::Ok {
+            check.message = format!("{} will be created by ceramic", path.display());
+        }
+        return check;
+    }
+    let fix = format!(
+        "Make {} and {} readable and writable by the user running ceramic",
+        path.display(),
+        dir.display()
+    );
+    if let Err(e) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+    {
+        return DoctorCheck::error(
+            name,
+            format!("cannot open {} for writing: {}", path.display(), e),
+            fix,
+        );
+    }
+    // sqlite writes its journal next to the database
+    if !is_writable(dir).await {
+        return DoctorCheck::error(name, format!("{} is not writable", dir.display()), fix);
+    }
+    DoctorCheck::ok(name, format!("{} is readable and writable", path.display()))
+}
+
+async fn check_db(working_directory: &Path, cfg: &Config) -> DoctorCheck {
+    let name = "database";
+    if let IndexingDb::Sqlite(path) = &cfg.indexing.db {
+        if !resolve(working_directory, path).exists() {
+            return DoctorCheck::skipped(name, "sqlite database has not been created");
+        }
+    }
+    match verify_db::verify(cfg).await {
+        Ok(()) => DoctorCheck::ok(name, format!("connected to {}", cfg.indexing.db)),
+        Err(e) => DoctorCheck::error(
+            name,
+            e.to_string(),
+            "Check that the database is running and indexing.db is correct, see https://github.com/ceramicstudio/wheel#setting-up-postgres",
+        ),
+    }
+}
+
+async fn check_ipfs_host(cfg: &Config) -> DoctorCheck {
+    let name = "ipfs";
+    let client = match reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return DoctorCheck::warning(name, e.to_string(), "Check ipfs manually"),
+    };
+    match check_ipfs(&client, cfg).await {
+        CheckResult::Passed => DoctorCheck::ok(name, "ipfs api is reachable"),
+        CheckResult::Skipped(reason) => DoctorCheck::skipped(name, reason),
+        CheckResult::Failed(e) => DoctorCheck::error(
+            name,
+            e,
+            "Start the ipfs node, or correct the ipfs host in the wheel config",
+        ),
+        CheckResult::NotRun => DoctorCheck::skipped(name, "not run"),
+    }
+}
+
+fn check_daemon_config(working_directory: &Path, cfg: &Config) -> DoctorCheck {
+    let name = "daemon config";
+    let file = working_directory.join(DAEMON_CONFIG_FILE);
+    let fix = "Rewrite it from the wheel config with `wheel restart`, or `wheel start` if ceramic is not running";
+    let expected =
+        match DaemonConfig::try_from(cfg.clone()).and_then(|d| Ok(serde_json::to_value(d)?)) {
+            Ok(v) => v,
+            Err(e) => {
+                return DoctorCheck::error(
+                    name,
+                    format!("cannot create daemon config: {}", e),
+                    "Fix the wheel config, see `wheel lint`",
+                )
+            }
+        };
+    let contents = match std::fs::read_to_string(&file) {
+        Ok(c) => c,
+        Err(_) => {
+            return DoctorCheck::warning(
+                name,
+                format!("{} has not been written", file.display()),
+                fix,
+            )
+        }
+    };
+    let actual: serde_json::Value = match serde_json::from_str(&contents) {
+        Ok(v) => v,
+        Err(e) => {
+            return DoctorCheck::error(name, format!("{} is invalid: {}", file.display(), e), fix)
+        }
+    };
+    if actual == expected {
+        return DoctorCheck::ok(name, format!("{} matches the wheel config", file.display()));
+    }
+    let mut sections: Vec<_> = expected
+        .as_object()
+        .into_iter()
+        .chain(actual.as_object())
+        .flat_map(|o| o.keys())
+        .filter(|k| expected.get(k.as_str()) != actual.get(k.as_str()))
+        .cloned()
+        .collect();
+    sections.sort();
+    sections.dedup();
+    DoctorCheck::warning(
+        name,
+        format!(
+            "{} differs from the wheel config in {}",
+            file.display(),
+            sections.join(", ")
+        ),
+        fix,
+    )
+}
+
+fn check_networks(working_directory: &Path, networks_file: Option<&Path>) -> DoctorCheck {
+    let file = match networks_file
+        .map(Path::to_path_buf)
+        .or_else(|| find_networks_file(working_directory))
+    {
+        Some(file) => file,
+        None => {
+            return DoctorCheck::skipped("networks", "no networks file, using built-in networks")
+        }
+    };
+    match NetworkRegistry::load(&file) {
+        Ok(registry) => DoctorCheck::ok(
+            "networks",
+            format!(
+                "loaded {}, networks are {}",
+                file.display(),
+                registry.names().join(", ")
+            ),
+        ),
+        Err(e) => DoctorCheck::error(
+            "networks",
+            e.to_string(),
+            format!("Fix or remove {}", file.display()),
+        ),
+    }
+}
+
+/// Check the environment and, if the config loads, the project
+pub async fn diagnose(
+    working_directory: &Path,
+    cfg_file_path: &Path,
+    networks_file: Option<&Path>,
+    layers: &ConfigLayers,
+) -> DoctorReport {
+    let mut checks = check_tools().await;
+    checks.push(check_networks(working_directory, networks_file));
+    let cfg = match layers.load_file(cfg_file_path) {
+        Ok(layered) => {
+            checks.push(DoctorCheck::ok(
+                "config",
+                format!("loaded {}", cfg_file_path.display()),
+            ));
+            layered.config
+        }
+        Err(e) => {
+            checks.push(DoctorCheck::error(
+                "config",
+                format!("cannot load {}: {}", cfg_file_path.display(), e),
+                "Run wheel to set up a project, or pass --config",
+            ));
+            return DoctorReport { checks };
+        }
+    };
+    let diagnostics = cfg.validate();
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if errors > 0 {
+        checks.push(DoctorCheck::error(
+            "config validation",
+            format!("{} errors", errors),
+            "Run `wheel lint` for details",
+        ));
+    } else if !diagnostics.is_empty() {
+        checks.push(DoctorCheck::warning(
+            "config validation",
+            format!("{} warnings", diagnostics.len()),
+            "Run `wheel lint` for details",
+        ));
+    } else {
+        checks.push(DoctorCheck::ok("config validation", "no problems found"));
+    }
+    checks.push(check_port(working_directory, &cfg).await);
+    checks.extend(check_directories(working_directory, &cfg).await);
+    checks.push(check_sqlite(working_directory, &cfg).await);
+    checks.push(check_db(working_directory, &cfg).await);
+    checks.push(check_ipfs_host(&cfg).await);
+    checks.push(check_daemon_config(working_directory, &cfg));
+    DoctorReport { checks }
+}
@@ -1,18 +1,42 @@
 use inquire::*;
 use std::path::Path;
-use std::process::{ExitStatus, Stdio};
+use std::process::Stdio;
 use tokio::task::JoinHandle;
 
-use crate::install::log_async_errors;
+use crate::daemon_log::{DaemonLog, Stream};
 use crate::install::npm::npm_install_package;
 use crate::install::verify_db;
+use crate::lifecycle::signal_process;
+use crate::readiness::wait_until_ready;
+use crate::supervisor::SHUTDOWN_TIMEOUT;
+use crate::DaemonOptions;
 use ceramic_config::Config;
-use spinners::{Spinner, Spinners};
 use tokio::process::Command;
 
-enum CeramicStatus {
-    Complete(Option<ExitStatus>),
-    HttpComplete(reqwest::Result<reqwest::Response>),
+/// Stop a ceramic that never became ready, killing it if it has not exited after the timeout
+async fn stop_unready(pid: u32, child: &mut JoinHandle<()>) -> anyhow::Result<()> {
+    log::info!("Stopping ceramic with pid {}", pid);
+    signal_process(pid, "TERM").await?;
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut *child)
+        .await
+        .is_err()
+    {
+        log::warn!(
+            "Ceramic did not exit within {}s, killing it",
+            SHUTDOWN_TIMEOUT.as_secs()
+        );
+        signal_process(pid, "KILL").await?;
+        let _ = child.await;
+    }
+    Ok(())
+}
+
+/// Health check url for the ceramic http api
+pub fn health_check_url(cfg: &Config) -> String {
+    format!(
+        "http://{}:{}/api/v0/node/healthcheck",
+        cfg.http_api.hostname, cfg.http_api.port
+    )
 }
 
 pub async fn install_ceramic_daemon(
@@ -21,6 +45,7 @@ pub async fn install_ceramic_daemon(
     version: &Option<semver::Version>,
     ceramic_config_file: &Path,
     start_ceramic: Option<bool>,
+    daemon: DaemonOptions,
 ) -> anyhow::Result<Option<JoinHandle<()>>> {
     verify_db::verify(&cfg).await?;
 
@@ -62,6 +87,7 @@ pub async fn install_ceramic_daemon(
             ceramic_config_file.display(),
             ceramic_path.display()
         );
+        let daemon_log = DaemonLog::open(working_directory, daemon.output).await?;
         let mut cmd = Command::new("sh");
 
         let mut process = cmd
@@ -76,62 +102,34 @@ pub async fn install_ceramic_daemon(
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?;
+        let pid = process.id();
+        let captured = daemon_log.capture(&mut process);
 
         let (tx, mut rx) = tokio::sync::mpsc::channel(1);
-        let opt_child = Some(tokio::spawn(async move {
-            let err = process.stderr.take();
+        let log = daemon_log.clone();
+        let mut child = tokio::spawn(async move {
             if let Ok(exit) = process.wait().await {
+                let _ = captured.await;
                 let _ = tx.send(exit.clone()).await;
+                log.write(Stream::Wheel, &format!("Ceramic {}", exit)).await;
                 log::info!(
-                    "\nCeramic exited with code {}",
-                    exit.code().unwrap_or_else(|| 0)
+                    "\nCeramic exited with code {}, output is in {}",
+                    exit.code().unwrap_or_else(|| 0),
+                    log.path().display()
                 );
-                if !exit.success() {
-                    if let Some(err) = err {
-                        log_async_errors(err).await;
-                    }
-                }
             }
-        }));
-
-        let url = format!(
-            "http://{}:{}/api/v0/node/healthcheck",
-            cfg.http_api.hostname, cfg.http_api.port
-        );
-
-        let mut sp = Spinner::new(Spinners::Star2, "Waiting for ceramic to start".into());
-
-        loop {
-            let r = tokio::select! {
-                r = rx.recv() => {
-                    CeramicStatus::Complete(r)
-                }
-                r = reqwest::get(&url) => {
-                    CeramicStatus::HttpComplete(r)
-                }
-            };
-            match r {
-                CeramicStatus::Complete(_) => {
-                    anyhow::bail!("Ceramic failed to start");
-                }
-                CeramicStatus::HttpComplete(r) => {
-                    match r {
-                        Ok(r) => {
-                            log::debug!("Ceramic responded with status {}", r.status());
-                            if r.status().is_success() {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            log::debug!("Ceramic failed to respond with error {}", e);
-                        }
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                }
+        });
+
+        if let Err(e) =
+            wait_until_ready(cfg, &mut rx, daemon.ready_timeout, daemon_log.path()).await
+        {
+            // nothing records this ceramic, so it would keep the port once wheel exits
+            if let Some(pid) = pid {
+                stop_unready(pid, &mut child).await?;
             }
+            return Err(e);
         }
-        sp.stop_with_newline();
-        opt_child
+        Some(child)
     } else {
         None
     };
@@ -142,7 +140,8 @@ When you would like to run ceramic please run
 
     ./ceramic daemon --config {}
 
-from the directory {}. For more information on the Ceramic http api see https://developers.ceramic.network/build/http/api/
+from the directory {}, or use `wheel generate systemd` to run it as a service. For more information on the Ceramic
+http api see https://developers.ceramic.network/build/http/api/
         "#,
         ceramic_config_file.display(),
         working_directory.display()
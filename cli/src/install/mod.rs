@@ -2,21 +2,11 @@ pub mod ceramic_app_template;
 pub mod ceramic_daemon;
 pub mod compose_db;
 mod npm;
-mod verify_db;
+pub mod verify_db;
 
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-
-pub async fn log_async_errors<T>(out: T)
-where
-    T: tokio::io::AsyncRead + Unpin,
-{
-    let mut lines = tokio::io::BufReader::new(out).lines();
-    while let Ok(Some(line)) = lines.next_line().await {
-        log::error!("    {}", line.trim())
-    }
-}
+use tokio::io::AsyncWriteExt;
 
 pub async fn create_invoke_script(
     path_to_cmd: impl AsRef<Path>,
@@ -36,7 +26,7 @@ pub async fn create_invoke_script(
             r#"
 #!/usr/bin/env sh
 {}
-node {} "$@"
+exec node {} "$@"
 "#,
             pre,
             path_to_cmd.as_ref().display(),
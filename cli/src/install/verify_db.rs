@@ -11,8 +11,9 @@ pub async fn verify(cfg: &Config) -> anyhow::Result<()> {
         "Verifying database connection using connection string {}",
         cfg.indexing.db
     );
-    if cfg.indexing.db.starts_with("postgres") {
-        match sqlx::postgres::PgConnection::connect(&cfg.indexing.db).await {
+    let db = cfg.indexing.db.url()?;
+    if db.starts_with("postgres") {
+        match sqlx::postgres::PgConnection::connect(&db).await {
             Err(e) => {
                 log::error!(
                     "Failed to connect to postgres, aborting daemon startup\n    {}",
@@ -32,7 +33,7 @@ pub async fn verify(cfg: &Config) -> anyhow::Result<()> {
 If you want to switch  networks, please follow the removal instructions at
 https://blog.ceramic.network/composedb-beta-update-model-versioning-release/ and
 then recreate following https://github.com/3box/wheel#setting-up-postgres"#,
-                            cfg.network.id,
+                            cfg.network,
                             network,
                             db_network
                         );
@@ -42,11 +43,11 @@ then recreate following https://github.com/3box/wheel#setting-up-postgres"#,
             }
         }
     } else {
-        let (_, path) = cfg.indexing.db.split_once("://").unwrap();
+        let (_, path) = db.split_once("://").unwrap();
         let p = PathBuf::from(path);
         log::info!("Verifying sqlite path exists at {}", p.display());
         if tokio::fs::try_exists(p).await? {
-            match sqlx::sqlite::SqliteConnection::connect(&cfg.indexing.db).await {
+            match sqlx::sqlite::SqliteConnection::connect(&db).await {
                 Ok(mut c) => {
                     if let Ok(Some(res)) = c.fetch_optional(SELECT_NETWORK_OPTION).await {
                         let db_network: String = res.get(VALUE_INDEX);
@@ -57,7 +58,7 @@ then recreate following https://github.com/3box/wheel#setting-up-postgres"#,
 
 If you want to switch networks, please follow the removal instructions at
 https://blog.ceramic.network/composedb-beta-update-model-versioning-release/."#,
-                                cfg.network.id,
+                                cfg.network,
                                 network,
                                 db_network
                             );
@@ -1,14 +1,30 @@
+mod config_edit;
+mod daemon_log;
 mod did;
+mod doctor;
 mod install;
+mod lifecycle;
 mod prompt;
+mod readiness;
+mod supervisor;
 
+pub use crate::daemon_log::{DaemonOutput, LogFilter};
 pub use crate::did::DidAndPrivateKey;
-pub use ceramic_config::NetworkIdentifier;
-use ceramic_config::{Anchor, CasAuth, Config};
+pub use crate::doctor::{CheckStatus, DoctorCheck, DoctorReport};
+pub use crate::lifecycle::DEFAULT_STOP_TIMEOUT;
+pub use crate::prompt::ConfigSection;
+pub use crate::readiness::{ReadinessReport, DEFAULT_READY_TIMEOUT, NOT_READY_EXIT_CODE};
+pub use crate::supervisor::SupervisorOptions;
+pub use ceramic_config::{
+    find_config_file, ConfigLayers, EnvFormat, NetworkDefinition, NetworkIdentifier,
+    NetworkRegistry, ResourceProfile,
+};
+use ceramic_config::{Anchor, CasAuth, Config, ConfigFormat, ConfigSource, IndexingDb, Secret};
 use inquire::*;
 use prompt::project::Project;
 use ssi::did::Document;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::{io::AsyncWriteExt, task::JoinHandle};
 
 #[derive(Default)]
@@ -18,6 +34,15 @@ pub struct Versions {
     pub template_branch: Option<String>,
 }
 
+/// How wheel runs ceramic when it starts it during setup
+#[derive(Clone, Copy, Debug)]
+pub struct DaemonOptions {
+    /// Time to wait for ceramic to become ready
+    pub ready_timeout: Duration,
+    /// Daemon output echoed by wheel, all output is written to the daemon log
+    pub output: DaemonOutput,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum DefaultChoice {
     Keep,
@@ -38,8 +63,11 @@ impl std::fmt::Display for DefaultChoice {
 pub async fn interactive_default(
     working_directory: PathBuf,
     versions: Versions,
+    layers: ConfigLayers,
+    networks: NetworkRegistry,
+    daemon: DaemonOptions,
 ) -> anyhow::Result<Option<JoinHandle<()>>> {
-    let network_identifier = NetworkIdentifier::InMemory;
+    let network = NetworkRegistry::for_identifier(&NetworkIdentifier::InMemory);
     let project = Project {
         name: "ceramic-app".to_string(),
         path: working_directory.join("ceramic-app"),
@@ -58,7 +86,7 @@ pub async fn interactive_default(
 "#,
         project.name,
         project.path.display(),
-        network_identifier,
+        network,
         did_sk_path.display()
     );
 
@@ -78,7 +106,9 @@ pub async fn interactive_default(
             log::info!("Exiting wheel");
             std::process::exit(0);
         }
-        DefaultChoice::Change => interactive(working_directory, versions).await,
+        DefaultChoice::Change => {
+            interactive(working_directory, versions, layers, networks, daemon).await
+        }
         DefaultChoice::Keep => {
             if !tokio::fs::try_exists(&project.path).await? {
                 log::info!(
@@ -88,15 +118,10 @@ pub async fn interactive_default(
                 tokio::fs::create_dir_all(&project.path).await?;
             }
             let doc = DidAndPrivateKey::generate(Some(did_sk_path)).await?;
-            let cfg_file_path = project.path.join("ceramic.json");
-            let mut cfg = get_or_create_config(
-                &project,
-                &network_identifier,
-                None,
-                &project.path,
-                &cfg_file_path,
-            )
-            .await?;
+            let cfg_file_path = find_config_file(&project.path);
+            let mut cfg =
+                get_or_create_config(&project, &network, None, &project.path, &cfg_file_path)
+                    .await?;
 
             cfg.http_api.admin_dids.push(doc.did().to_string());
 
@@ -106,11 +131,13 @@ pub async fn interactive_default(
                 cfg_file_path,
                 doc,
                 versions,
+                layers,
                 true,
                 false,
                 true,
                 true,
                 true,
+                daemon,
             )
             .await
         }
@@ -120,22 +147,25 @@ pub async fn interactive_default(
 pub async fn interactive(
     working_directory: PathBuf,
     versions: Versions,
+    layers: ConfigLayers,
+    networks: NetworkRegistry,
+    daemon: DaemonOptions,
 ) -> anyhow::Result<Option<JoinHandle<()>>> {
-    let network_identifier = Select::new(
+    let network = Select::new(
         "Project Type",
-        vec![
-            NetworkIdentifier::InMemory,
-            NetworkIdentifier::Dev,
-            NetworkIdentifier::Clay,
-            NetworkIdentifier::Mainnet,
-        ],
+        networks
+            .networks()
+            .iter()
+            .filter(|n| !(n.is_builtin() && n.daemon_network == NetworkIdentifier::Local))
+            .cloned()
+            .collect(),
     )
     .with_help_message(r#"InMemory is recommended when trying out Ceramic and ComposeDB (but nodes will not anchor).
 Other network types will require to setup up authentication with CAS (Ceramic Anchoring Service).
 Selection is used to setup project defaults"#)
     .prompt()?;
 
-    log::info!("Starting configuration for {} project", network_identifier);
+    log::info!("Starting configuration for {} project", network);
 
     let project = prompt::project::configure_project(&working_directory).await?;
 
@@ -164,51 +194,29 @@ Selection is used to setup project defaults"#)
     };
 
     let doc = prompt::did::prompt(&project.path).await?;
-    let cas_auth = if NetworkIdentifier::InMemory == network_identifier {
+    let cas_auth = if NetworkIdentifier::InMemory == network.daemon_network {
         None
     } else {
-        prompt::cas_auth::prompt(&doc, &network_identifier).await?
+        prompt::cas_auth::prompt(&doc, &network).await?
     };
 
-    let cfg_file_path = project.path.join("ceramic.json");
+    let cfg_file_path = find_config_file(&project.path);
     let cfg_file_path = Text::new("Wheel config file location")
         .with_default(&cfg_file_path.display().to_string())
         .prompt()?;
     let cfg_file_path = PathBuf::from(cfg_file_path);
-    let mut cfg = get_or_create_config(
-        &project,
-        &network_identifier,
-        cas_auth,
-        &project.path,
-        &cfg_file_path,
-    )
-    .await?;
+    let mut cfg =
+        get_or_create_config(&project, &network, cas_auth, &project.path, &cfg_file_path).await?;
 
     cfg.http_api.admin_dids.push(doc.did().to_string());
 
     if with_app_template {
-        if NetworkIdentifier::InMemory == network_identifier {
+        if NetworkIdentifier::InMemory == network.daemon_network {
             cfg.http_api.cors_allowed_origins = vec![".*".to_string()];
         }
     }
 
-    match network_identifier {
-        NetworkIdentifier::InMemory => {
-            prompt::prompt(&project.path, &mut cfg, &doc).await?;
-        }
-        NetworkIdentifier::Local => {
-            // TODO: prompt for local config
-        }
-        NetworkIdentifier::Dev => {
-            prompt::prompt(&project.path, &mut cfg, &doc).await?;
-        }
-        NetworkIdentifier::Clay => {
-            prompt::prompt(&project.path, &mut cfg, &doc).await?;
-        }
-        NetworkIdentifier::Mainnet => {
-            prompt::prompt(&project.path, &mut cfg, &doc).await?;
-        }
-    }
+    prompt::prompt(&project.path, &mut cfg, &doc).await?;
 
     finish_setup(
         project,
@@ -216,11 +224,13 @@ Selection is used to setup project defaults"#)
         cfg_file_path,
         doc,
         versions,
+        layers,
         with_ceramic,
         true,
         with_composedb,
         with_app_template,
         false,
+        daemon,
     )
     .await
 }
@@ -233,12 +243,18 @@ pub struct DidOptions {
 pub struct QuietOptions {
     pub project_name: Option<String>,
     pub working_directory: PathBuf,
-    pub network_identifier: NetworkIdentifier,
+    pub network: NetworkDefinition,
     pub versions: Versions,
     pub did: Option<DidOptions>,
     pub with_ceramic: bool,
     pub with_composedb: bool,
     pub with_app_template: bool,
+    pub layers: ConfigLayers,
+    /// Self-hosted CAS to anchor with, instead of the default for the network
+    pub cas_url: Option<String>,
+    pub cas_auth_method: Option<String>,
+    pub ethereum_rpc_url: Option<String>,
+    pub daemon: DaemonOptions,
 }
 
 pub async fn quiet(opts: QuietOptions) -> anyhow::Result<Option<JoinHandle<()>>> {
@@ -264,14 +280,14 @@ pub async fn quiet(opts: QuietOptions) -> anyhow::Result<Option<JoinHandle<()>>>
     } else {
         DidAndPrivateKey::generate(Some(project.path.join("admin.sk"))).await?
     };
-    let cas_auth = Anchor::url_for_network(&opts.network_identifier).map(|url| {
+    let cas_auth = opts.network.cas_url.clone().map(|url| {
         let pk = did.cas_auth();
         CasAuth { url, pk: Some(pk) }
     });
-    let cfg_file_path = project.path.join("ceramic.json");
+    let cfg_file_path = find_config_file(&project.path);
     let mut cfg = get_or_create_config(
         &project,
-        &opts.network_identifier,
+        &opts.network,
         cas_auth,
         &project.path,
         &cfg_file_path,
@@ -279,6 +295,22 @@ pub async fn quiet(opts: QuietOptions) -> anyhow::Result<Option<JoinHandle<()>>>
     .await?;
 
     cfg.http_api.admin_dids.push(did.did().to_string());
+    if let Some(url) = opts.cas_url {
+        let auth_method = opts.cas_auth_method.unwrap_or_else(|| "did".to_string());
+        let private_seed_url = if auth_method == "did" {
+            Some(Secret::new(did.cas_auth()))
+        } else {
+            None
+        };
+        cfg.anchor = Anchor::SelfHosted {
+            url,
+            auth_method: Some(auth_method),
+            private_seed_url,
+        };
+    }
+    if opts.ethereum_rpc_url.is_some() {
+        cfg.ethereum_rpc_url = opts.ethereum_rpc_url;
+    }
 
     finish_setup(
         project,
@@ -286,11 +318,13 @@ pub async fn quiet(opts: QuietOptions) -> anyhow::Result<Option<JoinHandle<()>>>
         cfg_file_path,
         did,
         opts.versions,
+        opts.layers,
         opts.with_ceramic,
         true,
         opts.with_composedb,
         opts.with_app_template,
         true,
+        opts.daemon,
     )
     .await
 }
@@ -301,23 +335,21 @@ async fn finish_setup(
     cfg_file_path: PathBuf,
     doc: DidAndPrivateKey,
     versions: Versions,
+    layers: ConfigLayers,
     with_ceramic: bool,
     start_ceramic: bool,
     with_composedb: bool,
     with_app_template: bool,
     quiet: bool,
+    daemon: DaemonOptions,
 ) -> anyhow::Result<Option<JoinHandle<()>>> {
-    log::info!("Saving config to {}", cfg_file_path.display());
-    let mut f = tokio::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .append(false)
-        .truncate(true)
-        .open(&cfg_file_path)
-        .await?;
-    f.write_all(serde_json::to_string_pretty(&cfg)?.as_bytes())
-        .await?;
-    f.flush().await?;
+    write_config(&cfg_file_path, &cfg).await?;
+
+    let layered = layers.apply(&cfg, ConfigSource::File(cfg_file_path.clone()))?;
+    for (path, source) in layered.overrides() {
+        log::info!("Using {} from {}", path, source);
+    }
+    let cfg = layered.config;
 
     let daemon_config_file = write_daemon_config(&project.path, &cfg).await?;
 
@@ -330,6 +362,7 @@ async fn finish_setup(
             &versions.ceramic,
             &daemon_config_file,
             start_ceramic,
+            daemon,
         )
         .await?
     } else {
@@ -356,35 +389,805 @@ async fn finish_setup(
         "Project {} created at {} for network {}",
         project.name,
         project.path.display(),
-        cfg.network.id
+        cfg.network
     );
 
     Ok(opt_child)
 }
 
+pub async fn import_daemon_config(
+    daemon_config_file: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    force: bool,
+) -> anyhow::Result<()> {
+    if !force && tokio::fs::try_exists(cfg_file_path.as_ref()).await? {
+        anyhow::bail!(
+            "Config file {} already exists, use --force to overwrite",
+            cfg_file_path.as_ref().display()
+        );
+    }
+    log::info!(
+        "Importing daemon config from {}",
+        daemon_config_file.as_ref().display()
+    );
+    let data = tokio::fs::read(daemon_config_file.as_ref()).await?;
+    let daemon_config: ceramic_config::DaemonConfig = serde_json::from_slice(data.as_slice())?;
+    let import = ceramic_config::import_daemon_config(daemon_config)?;
+    for dropped in &import.dropped {
+        log::warn!("Setting not imported, {}", dropped);
+    }
+    write_config(cfg_file_path.as_ref(), &import.config).await?;
+    if !import.dropped.is_empty() {
+        log::warn!(
+            "{} setting(s) could not be imported, please review {}",
+            import.dropped.len(),
+            cfg_file_path.as_ref().display()
+        );
+    }
+    Ok(())
+}
+
+pub async fn lint(
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    strict: bool,
+) -> anyhow::Result<()> {
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let report = cfg.lint();
+    log::info!(
+        "Linted {} for production readiness on {}, score {}/100",
+        cfg_file_path.as_ref().display(),
+        cfg.network,
+        report.score()
+    );
+    for severity in [
+        ceramic_config::Severity::Error,
+        ceramic_config::Severity::Warning,
+    ] {
+        let findings: Vec<_> = report.with_severity(severity).collect();
+        if !findings.is_empty() {
+            log::info!("\n{} {}(s):", findings.len(), severity);
+            for f in findings {
+                log::info!(
+                    "    [{}] {}: {}",
+                    f.rule,
+                    f.diagnostic.path,
+                    f.diagnostic.message
+                );
+            }
+        }
+    }
+    let warnings = report
+        .with_severity(ceramic_config::Severity::Warning)
+        .count();
+    if report.has_errors() || (strict && warnings > 0) {
+        anyhow::bail!("Config is not ready for production");
+    }
+    Ok(())
+}
+
+pub async fn convert_config(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    force: bool,
+) -> anyhow::Result<()> {
+    if !force && tokio::fs::try_exists(output.as_ref()).await? {
+        anyhow::bail!(
+            "Config file {} already exists, use --force to overwrite",
+            output.as_ref().display()
+        );
+    }
+    let from = ConfigFormat::from_path(input.as_ref());
+    let to = ConfigFormat::from_path(output.as_ref());
+    let data = tokio::fs::read(input.as_ref()).await?;
+    let cfg = ceramic_config::migrate(from.parse(data.as_slice())?)?.config;
+    let roundtrip = ceramic_config::migrate(to.parse(to.render(&cfg)?.as_bytes())?)?.config;
+    if serde_json::to_value(&cfg)? != serde_json::to_value(&roundtrip)? {
+        anyhow::bail!(
+            "Config cannot be converted from {} to {} without loss",
+            from,
+            to
+        );
+    }
+    log::info!(
+        "Converting {} ({}) to {} ({})",
+        input.as_ref().display(),
+        from,
+        output.as_ref().display(),
+        to
+    );
+    write_config(output.as_ref(), &cfg).await?;
+    Ok(())
+}
+
+pub async fn config_sources(
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+) -> anyhow::Result<()> {
+    let layered = layers.load_file(cfg_file_path.as_ref())?;
+    let doc = layered.config.redacted();
+    for (path, source) in &layered.sources {
+        let value = ceramic_config::lookup(&doc, path).unwrap_or(&serde_json::Value::Null);
+        log::info!("{} = {} ({})", path, value, source);
+    }
+    Ok(())
+}
+
+/// Print the project config, with secrets redacted
+pub async fn config_show(cfg_file_path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    println!("{}", serde_json::to_string_pretty(&cfg.redacted())?);
+    Ok(())
+}
+
+/// Print the value at a dotted path of the project config, with secrets redacted
+pub async fn config_get(cfg_file_path: impl AsRef<Path>, path: &str) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    let doc = cfg.redacted();
+    match ceramic_config::lookup(&doc, path) {
+        Some(serde_json::Value::String(s)) => println!("{}", s),
+        Some(value) => println!("{}", serde_json::to_string_pretty(value)?),
+        None => anyhow::bail!("Config setting {} is not set", path),
+    }
+    Ok(())
+}
+
+/// Set a dotted path of the project config to a value, parsed as json where possible
+pub async fn config_set(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    path: &str,
+    value: &str,
+    restart: Option<bool>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    let (updated, path) = ceramic_config::set_setting(&cfg, path, value)?;
+    log_setting_change(&cfg, &updated, &path);
+    apply_config_change(
+        working_directory.as_ref(),
+        cfg_file_path.as_ref(),
+        layers,
+        &updated,
+        restart,
+        ready_timeout,
+    )
+    .await
+}
+
+/// Reset a dotted path of the project config to its default
+pub async fn config_unset(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    path: &str,
+    restart: Option<bool>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    let (updated, path) = ceramic_config::unset_setting(&cfg, path)?;
+    log_setting_change(&cfg, &updated, &path);
+    apply_config_change(
+        working_directory.as_ref(),
+        cfg_file_path.as_ref(),
+        layers,
+        &updated,
+        restart,
+        ready_timeout,
+    )
+    .await
+}
+
+/// Edit the project config in `$VISUAL` or `$EDITOR`
+pub async fn config_edit(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    restart: Option<bool>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    let updated = if let Some(updated) = config_edit::edit(cfg_file_path.as_ref()).await? {
+        updated
+    } else {
+        log::info!("Config edit abandoned, no changes saved");
+        return Ok(());
+    };
+    if serde_json::to_value(&cfg)? == serde_json::to_value(&updated)? {
+        log::info!("No changes to {}", cfg_file_path.as_ref().display());
+        return Ok(());
+    }
+    apply_config_change(
+        working_directory.as_ref(),
+        cfg_file_path.as_ref(),
+        layers,
+        &updated,
+        restart,
+        ready_timeout,
+    )
+    .await
+}
+
+/// The config file over defaults, without environment or flag layers, which are not saved
+fn file_config(cfg_file_path: &Path) -> anyhow::Result<Config> {
+    Ok(ConfigLayers::default().load_file(cfg_file_path)?.config)
+}
+
+/// Walk through the advanced configuration of an existing project again, with every prompt
+/// defaulting to the current config. Only the given section is configured, if any. The changes
+/// are shown before they are saved.
+pub async fn reconfigure(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    section: Option<ConfigSection>,
+    restart: Option<bool>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    let cfg = file_config(cfg_file_path.as_ref())?;
+    let mut updated = cfg.clone();
+    let sections = match section {
+        Some(section) => vec![section],
+        None => ConfigSection::all().to_vec(),
+    };
+    for section in sections {
+        log::info!("Configuring {}", section);
+        prompt::ceramic_advanced_config::configure_section(
+            &mut updated,
+            section,
+            None,
+            working_directory.as_ref(),
+        )
+        .await?;
+    }
+
+    let changes = ceramic_config::diff(&cfg, &updated);
+    if changes.is_empty() {
+        log::info!("No changes to {}", cfg_file_path.as_ref().display());
+        return Ok(());
+    }
+    log::info!("Changes to {}:", cfg_file_path.as_ref().display());
+    for change in &changes {
+        log::info!("    {}", change);
+    }
+    let save = Confirm::new("Save these changes?")
+        .with_default(true)
+        .prompt()?;
+    if !save {
+        log::info!("Changes discarded");
+        return Ok(());
+    }
+    apply_config_change(
+        working_directory.as_ref(),
+        cfg_file_path.as_ref(),
+        layers,
+        &updated,
+        restart,
+        ready_timeout,
+    )
+    .await
+}
+
+fn log_setting_change(before: &Config, after: &Config, path: &str) {
+    let before = before.redacted();
+    let after = after.redacted();
+    let value = |doc| {
+        ceramic_config::lookup(doc, path)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "unset".to_string())
+    };
+    log::info!(
+        "Changing {} from {} to {}",
+        path,
+        value(&before),
+        value(&after)
+    );
+}
+
+/// Save a changed project config and regenerate the daemon config from it, offering to restart
+/// a running daemon so it picks up the change
+async fn apply_config_change(
+    working_directory: &Path,
+    cfg_file_path: &Path,
+    layers: &ConfigLayers,
+    cfg: &Config,
+    restart_daemon: Option<bool>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    let effective = layers
+        .apply(cfg, ConfigSource::File(cfg_file_path.to_path_buf()))?
+        .config;
+    // writing the daemon config validates the change, so an invalid change leaves both untouched
+    write_daemon_config(working_directory, &effective).await?;
+    write_config(cfg_file_path, cfg).await?;
+    if let Some(daemon) = lifecycle::running(working_directory).await? {
+        if config_edit::should_restart(restart_daemon)? {
+            let supervise = lifecycle::supervisor_options(working_directory).await?;
+            restart(
+                working_directory,
+                cfg_file_path,
+                layers,
+                DEFAULT_STOP_TIMEOUT,
+                supervise.as_ref(),
+                ready_timeout,
+            )
+            .await?;
+        } else {
+            log::info!(
+                "Ceramic with pid {} is still using the previous config, run wheel restart to apply the change",
+                daemon.pid
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn export_env(
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    format: EnvFormat,
+    secret_references: bool,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let env = cfg.env_vars(secret_references)?;
+    for secret in env.unexpanded_references(format) {
+        log::warn!(
+            "{} cannot be read by {} env files, it is written as is",
+            secret,
+            format
+        );
+    }
+    let rendered = env.render(format)?;
+    if let Some(output) = output {
+        log::info!("Saving {} environment to {}", format, output.display());
+        tokio::fs::write(&output, rendered).await?;
+    } else {
+        print!("{}", rendered);
+    }
+    Ok(())
+}
+
+pub async fn generate_compose(
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    output_directory: impl AsRef<Path>,
+    ceramic_version: Option<&semver::Version>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let tag = ceramic_version.map(|v| v.to_string());
+    let stack = ceramic_config::ComposeStack::from_config(&cfg, tag.as_deref())?;
+    validate_config(&stack.config, "not generating compose stack")?;
+    let compose_file = output_directory.as_ref().join("compose.yaml");
+    if !force && tokio::fs::try_exists(&compose_file).await? {
+        anyhow::bail!(
+            "Compose file {} already exists, use --force to overwrite",
+            compose_file.display()
+        );
+    }
+    tokio::fs::create_dir_all(output_directory.as_ref()).await?;
+    let daemon_config = stack.daemon_config()?;
+    tokio::fs::write(
+        output_directory
+            .as_ref()
+            .join(ceramic_config::COMPOSE_DAEMON_CONFIG_FILE),
+        serde_json::to_string_pretty(&daemon_config)?,
+    )
+    .await?;
+    log::info!("Saving compose file to {}", compose_file.display());
+    tokio::fs::write(&compose_file, stack.render()?).await?;
+    log::info!(
+        "Start the stack with\n\n    docker compose -f {} up -d\n",
+        compose_file.display()
+    );
+    Ok(())
+}
+
+pub async fn generate_kubernetes(
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    output: impl AsRef<Path>,
+    namespace: &str,
+    profile: ResourceProfile,
+    ceramic_version: Option<&semver::Version>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let tag = ceramic_version.map(|v| v.to_string());
+    validate_config(&cfg, "not generating kubernetes manifests")?;
+    let manifests =
+        ceramic_config::KubernetesManifests::from_config(&cfg, namespace, profile, tag.as_deref())?;
+    if !force && tokio::fs::try_exists(output.as_ref()).await? {
+        anyhow::bail!(
+            "Manifest file {} already exists, use --force to overwrite",
+            output.as_ref().display()
+        );
+    }
+    log::info!(
+        "Saving kubernetes manifests for namespace {} to {}",
+        manifests.namespace,
+        output.as_ref().display()
+    );
+    tokio::fs::write(output.as_ref(), manifests.render()?).await?;
+    log::info!(
+        "Apply the manifests with\n\n    kubectl apply -f {}\n",
+        output.as_ref().display()
+    );
+    Ok(())
+}
+
+pub async fn generate_systemd(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    output_directory: impl AsRef<Path>,
+    user: Option<String>,
+    env_file: Option<PathBuf>,
+    app_directory: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let working_directory = working_directory.as_ref().canonicalize()?;
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let daemon_config_file = working_directory.join("daemon_config.json");
+    if !tokio::fs::try_exists(working_directory.join("ceramic")).await? {
+        log::warn!(
+            "No ceramic invoke script in {}, run wheel setup there before starting the service",
+            working_directory.display()
+        );
+    }
+    let env_file = env_file.unwrap_or_else(|| working_directory.join("ceramic.env"));
+    let mut units = vec![ceramic_config::SystemdUnit::ceramic(
+        &cfg,
+        &working_directory,
+        &daemon_config_file,
+        Some(&env_file),
+    )
+    .with_user(user.clone())];
+    if let Some(app_directory) = app_directory {
+        units.push(
+            ceramic_config::SystemdUnit::demo_app(&app_directory.canonicalize()?).with_user(user),
+        );
+    }
+    // the service cannot create directories outside its writable paths, so they are made now
+    for dir in units.iter().flat_map(|u| &u.read_write_paths) {
+        if let Err(e) = tokio::fs::create_dir_all(dir).await {
+            log::warn!(
+                "Could not create {}, create it before starting the service: {}",
+                dir.display(),
+                e
+            );
+        }
+    }
+    tokio::fs::create_dir_all(output_directory.as_ref()).await?;
+    for unit in &units {
+        let path = output_directory.as_ref().join(&unit.name);
+        log::info!("Saving systemd unit to {}", path.display());
+        tokio::fs::write(&path, unit.render()).await?;
+    }
+    log::info!(
+        r#"
+To install the units, run
+
+    sudo cp {}/*.service /etc/systemd/system/
+    sudo systemctl daemon-reload
+    sudo systemctl enable --now {}
+"#,
+        output_directory.as_ref().display(),
+        units
+            .iter()
+            .map(|u| u.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    Ok(())
+}
+
+pub async fn start(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    supervise: Option<&SupervisorOptions>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    if let Some(daemon) = lifecycle::running(working_directory.as_ref()).await? {
+        anyhow::bail!("Ceramic is already running with pid {}", daemon.pid);
+    }
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let daemon_config_file = write_daemon_config(working_directory.as_ref(), &cfg).await?;
+    let pid = lifecycle::start(
+        working_directory.as_ref(),
+        &cfg,
+        &daemon_config_file,
+        supervise,
+        ready_timeout,
+    )
+    .await?;
+    log::info!(
+        "Ceramic is running on {} with pid {}, listening on port {}",
+        cfg.network,
+        pid,
+        cfg.http_api.port
+    );
+    Ok(())
+}
+
+pub async fn stop(working_directory: impl AsRef<Path>, timeout: Duration) -> anyhow::Result<()> {
+    if lifecycle::stop(working_directory.as_ref(), timeout).await? {
+        log::info!("Ceramic stopped");
+    } else {
+        log::info!(
+            "Ceramic is not running in {}",
+            working_directory.as_ref().display()
+        );
+    }
+    Ok(())
+}
+
+pub async fn restart(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    timeout: Duration,
+    supervise: Option<&SupervisorOptions>,
+    ready_timeout: Duration,
+) -> anyhow::Result<()> {
+    lifecycle::stop(working_directory.as_ref(), timeout).await?;
+    start(
+        working_directory,
+        cfg_file_path,
+        layers,
+        supervise,
+        ready_timeout,
+    )
+    .await
+}
+
+/// Run ceramic in the foreground, restarting it when it crashes. The daemon config is written
+/// from the wheel config unless an existing daemon config file is given.
+pub async fn supervise(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+    daemon_config_file: Option<PathBuf>,
+    opts: SupervisorOptions,
+    output: DaemonOutput,
+) -> anyhow::Result<()> {
+    let daemon_config_file = if let Some(file) = daemon_config_file {
+        file
+    } else {
+        let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+        write_daemon_config(working_directory.as_ref(), &cfg).await?
+    };
+    supervisor::supervise(
+        working_directory.as_ref().to_path_buf(),
+        daemon_config_file,
+        opts,
+        output,
+    )
+    .await
+}
+
+/// Check the environment and project for problems that would stop ceramic from running
+pub async fn doctor(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    networks_file: Option<&Path>,
+    layers: &ConfigLayers,
+    json: bool,
+) -> anyhow::Result<()> {
+    let report = doctor::diagnose(
+        working_directory.as_ref(),
+        cfg_file_path.as_ref(),
+        networks_file,
+        layers,
+    )
+    .await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report);
+    }
+    match report.errors() {
+        0 => Ok(()),
+        1 => anyhow::bail!("wheel doctor found 1 problem"),
+        n => anyhow::bail!("wheel doctor found {} problems", n),
+    }
+}
+
+/// Print the daemon log written by wheel, optionally following new output
+pub async fn logs(
+    working_directory: impl AsRef<Path>,
+    filter: &LogFilter,
+    follow: bool,
+) -> anyhow::Result<()> {
+    daemon_log::print_log(working_directory.as_ref(), filter, follow).await
+}
+
+pub async fn status(
+    working_directory: impl AsRef<Path>,
+    cfg_file_path: impl AsRef<Path>,
+    layers: &ConfigLayers,
+) -> anyhow::Result<()> {
+    let cfg = layers.load_file(cfg_file_path.as_ref())?.config;
+    let daemon = if let Some(daemon) = lifecycle::running(working_directory.as_ref()).await? {
+        daemon
+    } else if let Some(pid) = lifecycle::orphaned(working_directory.as_ref()).await? {
+        log::warn!(
+            "Ceramic is running with pid {} without its supervisor, run wheel stop to stop it",
+            pid
+        );
+        return Ok(());
+    } else {
+        log::info!(
+            "Ceramic is not running in {}",
+            working_directory.as_ref().display()
+        );
+        return Ok(());
+    };
+    let healthy = reqwest::get(install::ceramic_daemon::health_check_url(&cfg))
+        .await
+        .map(|r| r.status().is_success())
+        .unwrap_or(false);
+    log::info!(
+        r#"Ceramic is running
+    - Pid: {}
+    - Uptime: {}
+    - Network: {}
+    - Port: {}
+    - Health: {}"#,
+        daemon.pid,
+        lifecycle::format_uptime(daemon.uptime()),
+        cfg.network,
+        cfg.http_api.port,
+        if healthy { "ok" } else { "not responding" }
+    );
+    Ok(())
+}
+
+pub async fn schema(daemon: bool, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let schema = if daemon {
+        ceramic_config::daemon_config_schema()
+    } else {
+        ceramic_config::config_schema()
+    };
+    if let Some(output) = output {
+        log::info!("Saving schema to {}", output.display());
+        write_schema(output, &schema).await?;
+    } else {
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+    }
+    Ok(())
+}
+
+async fn write_schema(path: impl AsRef<Path>, schema: &serde_json::Value) -> anyhow::Result<()> {
+    tokio::fs::write(path.as_ref(), serde_json::to_string_pretty(schema)?).await?;
+    Ok(())
+}
+
+/// Write a wheel config in the format chosen by its extension, along with the schema it
+/// references for editor support
+async fn write_config(cfg_file_path: impl AsRef<Path>, cfg: &Config) -> anyhow::Result<()> {
+    let schema_file = cfg_file_path
+        .as_ref()
+        .with_file_name(ceramic_config::CONFIG_SCHEMA_FILE);
+    write_schema(&schema_file, &ceramic_config::config_schema()).await?;
+    log::info!("Saving config to {}", cfg_file_path.as_ref().display());
+    let rendered = ConfigFormat::from_path(cfg_file_path.as_ref())
+        .render_with_schema(cfg, &format!("./{}", ceramic_config::CONFIG_SCHEMA_FILE))?;
+    write_atomic(cfg_file_path.as_ref(), rendered.as_bytes()).await
+}
+
+/// Write a file by renaming a fully written temporary file over it, so readers never see a
+/// partially written file
+async fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp", name));
+    if tokio::fs::try_exists(&tmp).await? {
+        tokio::fs::remove_file(&tmp).await?;
+    }
+    // configs can hold resolved secrets, so new files are private and existing ones keep the
+    // permissions they were given
+    let mut f = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&tmp)
+        .await?;
+    if let Ok(metadata) = tokio::fs::metadata(path).await {
+        f.set_permissions(metadata.permissions()).await?;
+    }
+    f.write_all(contents).await?;
+    f.sync_all().await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Log validation problems, failing if there are errors. `action` says what is not done then.
+fn validate_config(cfg: &Config, action: &str) -> anyhow::Result<()> {
+    let diagnostics = cfg.validate();
+    for d in &diagnostics {
+        if d.is_error() {
+            log::error!("{}", d);
+        } else {
+            log::warn!("{}", d);
+        }
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        anyhow::bail!("Config is invalid, {}", action);
+    }
+    Ok(())
+}
+
 async fn write_daemon_config(
     working_directory: impl AsRef<Path>,
     cfg: &ceramic_config::Config,
 ) -> anyhow::Result<PathBuf> {
+    validate_config(cfg, "not writing daemon config")?;
     let cfg_file_path = working_directory.as_ref().join("daemon_config.json");
+    write_schema(
+        working_directory
+            .as_ref()
+            .join(ceramic_config::DAEMON_CONFIG_SCHEMA_FILE),
+        &ceramic_config::daemon_config_schema(),
+    )
+    .await?;
     log::info!("Saving daemon file to {}", cfg_file_path.display());
-    let daemon_config: ceramic_config::DaemonConfig = cfg.clone().into();
-    let mut f = tokio::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .append(false)
-        .open(&cfg_file_path)
-        .await?;
-    f.write_all(serde_json::to_string_pretty(&daemon_config)?.as_bytes())
-        .await?;
-    f.flush().await?;
+    let daemon_config = ceramic_config::DaemonConfig::try_from(cfg.clone())?;
+    // reference the schema like the wheel config does, the daemon ignores unknown keys
+    let mut doc = serde_json::to_value(&daemon_config)?;
+    if let serde_json::Value::Object(m) = &mut doc {
+        m.insert(
+            "$schema".to_string(),
+            serde_json::Value::from(format!("./{}", ceramic_config::DAEMON_CONFIG_SCHEMA_FILE)),
+        );
+    }
+    write_atomic(
+        &cfg_file_path,
+        serde_json::to_string_pretty(&doc)?.as_bytes(),
+    )
+    .await?;
     Ok(cfg_file_path)
 }
 
+/// Read a wheel config, upgrading it to the current version if needed. Upgraded configs are
+/// written back, keeping the original next to it as a backup.
+async fn read_config(cfg_file_path: impl AsRef<Path>) -> anyhow::Result<Config> {
+    let format = ConfigFormat::from_path(cfg_file_path.as_ref());
+    let data = tokio::fs::read(cfg_file_path.as_ref()).await?;
+    let migrated = ceramic_config::migrate(format.parse(data.as_slice())?)?;
+    if migrated.was_migrated() {
+        for migration in &migrated.applied {
+            log::info!("Migrated config {}", migration);
+        }
+        let extension = cfg_file_path
+            .as_ref()
+            .extension()
+            .map(|e| format!("{}.", e.to_string_lossy()))
+            .unwrap_or_default();
+        let backup = cfg_file_path.as_ref().with_extension(format!(
+            "{}v{}.bak",
+            extension,
+            migrated.original_version()
+        ));
+        log::info!(
+            "Upgraded config {} to version {}, original saved to {}",
+            cfg_file_path.as_ref().display(),
+            migrated.config.version,
+            backup.display()
+        );
+        tokio::fs::write(&backup, &data).await?;
+        write_config(cfg_file_path.as_ref(), &migrated.config).await?;
+    }
+    Ok(migrated.config)
+}
+
 async fn get_or_create_config(
     project: &Project,
-    network_identifier: &NetworkIdentifier,
+    network: &NetworkDefinition,
     cas_auth: Option<CasAuth>,
     working_directory: impl AsRef<Path>,
     cfg_file_path: impl AsRef<Path>,
@@ -394,17 +1197,12 @@ async fn get_or_create_config(
             "Initializing config with previous information from {}",
             cfg_file_path.as_ref().display()
         );
-        let data = tokio::fs::read(cfg_file_path.as_ref()).await?;
-        let mut cfg: Config = serde_json::from_slice(data.as_slice())?;
-        cfg.initialize(network_identifier, &project.name, cas_auth);
+        let mut cfg = read_config(cfg_file_path.as_ref()).await?;
+        cfg.initialize_network(network, &project.name, cas_auth);
         cfg
     } else {
-        let mut cfg = Config::new(network_identifier, &project.name, cas_auth);
-        if let NetworkIdentifier::InMemory
-        | NetworkIdentifier::Local
-        | NetworkIdentifier::Dev
-        | NetworkIdentifier::Clay = cfg.network.id
-        {
+        let mut cfg = Config::for_network(network, &project.name, cas_auth);
+        if cfg.allows_sqlite() {
             let db_path = working_directory
                 .as_ref()
                 .canonicalize()?
@@ -413,7 +1211,7 @@ async fn get_or_create_config(
                 tokio::fs::create_dir_all(&db_path).await?;
             }
             let db_path = db_path.join("ceramic.db");
-            cfg.indexing.db = format!("sqlite://{}", db_path.display());
+            cfg.indexing.db = IndexingDb::Sqlite(db_path);
         }
         cfg
     };
@@ -0,0 +1,294 @@
+use crate::daemon_log::LOG_FILE;
+use crate::readiness::wait_until_ready;
+use crate::supervisor::{SupervisorOptions, SHUTDOWN_TIMEOUT};
+use ceramic_config::Config;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime};
+use tokio::process::Command;
+
+/// File holding the pid of a ceramic daemon started by wheel
+pub const PID_FILE: &str = "ceramic.pid";
+/// File holding the pid of ceramic's own process group and of the supervisor running it, so a
+/// daemon left behind by a supervisor that was killed can still be found
+pub const DAEMON_PID_FILE: &str = "ceramic-daemon.pid";
+/// File holding the supervisor options of a daemon started with `--supervise`, so restarts
+/// after a config change keep supervising it
+pub const SUPERVISOR_FILE: &str = "ceramic-supervisor.json";
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Time `wheel stop` waits before killing ceramic, longer than the supervisor's own shutdown so
+/// the supervisor gets to stop ceramic first
+pub const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(SHUTDOWN_TIMEOUT.as_secs() + 15);
+
+/// A ceramic daemon started by wheel
+pub struct RunningDaemon {
+    pub pid: u32,
+    pub started: SystemTime,
+}
+
+impl RunningDaemon {
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed().unwrap_or_default()
+    }
+}
+
+pub fn pid_file(working_directory: &Path) -> PathBuf {
+    working_directory.join(PID_FILE)
+}
+
+/// Send a signal to the daemon's process group, so the node process started by the invoke script
+/// receives it too. Returns false if there is no such process.
+pub(crate) async fn signal(pid: u32, signal: &str) -> anyhow::Result<bool> {
+    let status = Command::new("kill")
+        .args([
+            format!("-{}", signal),
+            "--".to_string(),
+            format!("-{}", pid),
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+async fn is_alive(pid: u32) -> anyhow::Result<bool> {
+    signal(pid, "0").await
+}
+
+/// Send a signal to a single process, for processes that may not lead their own group
+pub(crate) async fn signal_process(pid: u32, signal: &str) -> anyhow::Result<bool> {
+    let status = Command::new("kill")
+        .args([format!("-{}", signal), pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+async fn is_process_alive(pid: u32) -> anyhow::Result<bool> {
+    signal_process(pid, "0").await
+}
+
+/// Supervisor options the running daemon was started with, None if it is not supervised
+pub async fn supervisor_options(
+    working_directory: &Path,
+) -> anyhow::Result<Option<SupervisorOptions>> {
+    let file = working_directory.join(SUPERVISOR_FILE);
+    if !tokio::fs::try_exists(&file).await? {
+        return Ok(None);
+    }
+    let data = tokio::fs::read(&file).await?;
+    Ok(Some(serde_json::from_slice(&data)?))
+}
+
+pub fn daemon_pid_file(working_directory: &Path) -> PathBuf {
+    working_directory.join(DAEMON_PID_FILE)
+}
+
+/// Record the daemon's process group and its supervisor
+pub(crate) async fn write_daemon_pid(
+    working_directory: &Path,
+    daemon: u32,
+    supervisor: u32,
+) -> anyhow::Result<()> {
+    tokio::fs::write(
+        daemon_pid_file(working_directory),
+        format!("{}\n{}\n", daemon, supervisor),
+    )
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn remove_daemon_pid(working_directory: &Path) {
+    let _ = tokio::fs::remove_file(daemon_pid_file(working_directory)).await;
+}
+
+/// Pid of a daemon whose supervisor is gone but which is still running, removing the pid file if
+/// the daemon has exited
+pub async fn orphaned(working_directory: &Path) -> anyhow::Result<Option<u32>> {
+    let file = daemon_pid_file(working_directory);
+    if !tokio::fs::try_exists(&file).await? {
+        return Ok(None);
+    }
+    let contents = tokio::fs::read_to_string(&file).await?;
+    let pids: Vec<u32> = contents
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid pid file {}", file.display()))?;
+    let (daemon, supervisor) = match pids[..] {
+        [daemon, supervisor] => (daemon, supervisor),
+        _ => anyhow::bail!("Invalid pid file {}", file.display()),
+    };
+    if !is_alive(daemon).await? {
+        log::debug!("Removing stale pid file {}", file.display());
+        tokio::fs::remove_file(&file).await?;
+        return Ok(None);
+    }
+    if is_process_alive(supervisor).await? {
+        return Ok(None);
+    }
+    Ok(Some(daemon))
+}
+
+/// Stop a daemon left running without its supervisor. Returns false if there was none.
+async fn stop_orphaned(working_directory: &Path, timeout: Duration) -> anyhow::Result<bool> {
+    let pid = if let Some(pid) = orphaned(working_directory).await? {
+        pid
+    } else {
+        return Ok(false);
+    };
+    log::warn!(
+        "Stopping ceramic with pid {}, which was left running without its supervisor",
+        pid
+    );
+    signal(pid, "TERM").await?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    while is_alive(pid).await? {
+        if tokio::time::Instant::now() >= deadline {
+            signal(pid, "KILL").await?;
+            break;
+        }
+        tokio::time::sleep(STOP_POLL_INTERVAL).await;
+    }
+    remove_daemon_pid(working_directory).await;
+    Ok(true)
+}
+
+/// The running daemon for a project, removing the pid file if the daemon is no longer running
+pub async fn running(working_directory: &Path) -> anyhow::Result<Option<RunningDaemon>> {
+    let file = pid_file(working_directory);
+    if !tokio::fs::try_exists(&file).await? {
+        return Ok(None);
+    }
+    let contents = tokio::fs::read_to_string(&file).await?;
+    let pid: u32 = contents
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid pid file {}", file.display()))?;
+    if !is_alive(pid).await? {
+        log::debug!("Removing stale pid file {}", file.display());
+        tokio::fs::remove_file(&file).await?;
+        return Ok(None);
+    }
+    let started = tokio::fs::metadata(&file).await?.modified()?;
+    Ok(Some(RunningDaemon { pid, started }))
+}
+
+/// Start the daemon in the background under `wheel supervise`, which writes its output to the
+/// daemon log, returning once it is ready. Without supervisor options it is not restarted.
+pub async fn start(
+    working_directory: &Path,
+    cfg: &Config,
+    daemon_config_file: &Path,
+    supervise: Option<&SupervisorOptions>,
+    ready_timeout: Duration,
+) -> anyhow::Result<u32> {
+    if let Some(daemon) = running(working_directory).await? {
+        anyhow::bail!("Ceramic is already running with pid {}", daemon.pid);
+    }
+    if let Some(pid) = orphaned(working_directory).await? {
+        anyhow::bail!(
+            "Ceramic is still running with pid {} without its supervisor, run wheel stop first",
+            pid
+        );
+    }
+    if !tokio::fs::try_exists(working_directory.join("ceramic")).await? {
+        anyhow::bail!(
+            "Ceramic is not installed in {}, please run wheel setup first",
+            working_directory.display()
+        );
+    }
+    let output_file = working_directory.join(LOG_FILE);
+    log::info!(
+        "Starting ceramic with config file {}, output is written to {}",
+        daemon_config_file.display(),
+        output_file.display()
+    );
+    let opts = supervise
+        .cloned()
+        .unwrap_or_else(SupervisorOptions::no_restarts);
+    let mut process = Command::new(std::env::current_exe()?)
+        .arg("--working-directory")
+        .arg(working_directory)
+        .args(["--daemon-output", "none", "supervise", "--daemon-config"])
+        .arg(daemon_config_file)
+        .args(opts.to_args())
+        .current_dir(working_directory)
+        .process_group(0)
+        .kill_on_drop(false)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let pid = process
+        .id()
+        .ok_or_else(|| anyhow::anyhow!("Ceramic exited immediately"))?;
+    tokio::fs::write(pid_file(working_directory), pid.to_string()).await?;
+    let supervisor_file = working_directory.join(SUPERVISOR_FILE);
+    if let Some(opts) = supervise {
+        tokio::fs::write(&supervisor_file, serde_json::to_vec_pretty(opts)?).await?;
+    } else if tokio::fs::try_exists(&supervisor_file).await? {
+        tokio::fs::remove_file(&supervisor_file).await?;
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move {
+        if let Ok(exit) = process.wait().await {
+            let _ = tx.send(exit).await;
+        }
+    });
+    if let Err(e) = wait_until_ready(cfg, &mut rx, ready_timeout, &output_file).await {
+        // the supervisor stops ceramic on SIGTERM, killing only the supervisor would leave ceramic
+        // running in its own process group
+        stop(working_directory, DEFAULT_STOP_TIMEOUT).await?;
+        return Err(e);
+    }
+    Ok(pid)
+}
+
+/// Stop the daemon with SIGTERM, killing it if it has not exited after the timeout. A daemon
+/// left running without its supervisor is stopped too. Returns false if the daemon was not
+/// running.
+pub async fn stop(working_directory: &Path, timeout: Duration) -> anyhow::Result<bool> {
+    let daemon = if let Some(daemon) = running(working_directory).await? {
+        daemon
+    } else {
+        return stop_orphaned(working_directory, timeout).await;
+    };
+    log::info!("Stopping ceramic with pid {}", daemon.pid);
+    signal(daemon.pid, "TERM").await?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    while is_alive(daemon.pid).await? {
+        if tokio::time::Instant::now() >= deadline {
+            log::warn!(
+                "Ceramic did not exit within {}s, killing it",
+                timeout.as_secs()
+            );
+            signal(daemon.pid, "KILL").await?;
+            break;
+        }
+        tokio::time::sleep(STOP_POLL_INTERVAL).await;
+    }
+    tokio::fs::remove_file(pid_file(working_directory)).await?;
+    // a killed supervisor leaves ceramic behind, which gets whatever is left of the timeout
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    stop_orphaned(working_directory, remaining).await?;
+    Ok(true)
+}
+
+/// Uptime as hours, minutes and seconds, e.g. `1h 2m 3s`
+pub fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
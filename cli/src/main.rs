@@ -3,29 +3,9 @@ use clap::{Parser, Subcommand, ValueEnum};
 use log::LevelFilter;
 use std::fmt::Formatter;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
-
-#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
-enum Network {
-    InMemory,
-    Local,
-    Dev,
-    Clay,
-    Mainnet,
-}
-
-impl std::fmt::Display for Network {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::InMemory => write!(f, "in-memory"),
-            Self::Local => write!(f, "local"),
-            Self::Dev => write!(f, "dev"),
-            Self::Clay => write!(f, "clay"),
-            Self::Mainnet => write!(f, "mainnet"),
-        }
-    }
-}
+use std::time::Duration;
 
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
 enum Setup {
@@ -65,18 +45,488 @@ enum DidCommand {
 struct QuietOptions {
     #[arg(long)]
     project_name: Option<String>,
-    #[arg(long, short = 'n', default_value_t = Network::Clay)]
-    network: Network,
+    #[arg(
+        long,
+        short = 'n',
+        default_value = "clay",
+        help = "Network name, one of in-memory, local, dev, clay, mainnet or a network from the networks file"
+    )]
+    network: String,
     #[command(subcommand)]
     did: DidCommand,
     #[arg(long, default_value_t = Setup::ComposeDB)]
     setup: Setup,
+    #[arg(
+        long,
+        help = "Anchor with a self-hosted CAS instead of the default CAS for the network"
+    )]
+    cas_url: Option<String>,
+    #[arg(
+        long,
+        requires = "cas_url",
+        help = "Auth method for the self-hosted CAS, defaults to did"
+    )]
+    cas_auth_method: Option<String>,
+    #[arg(long, help = "Ethereum RPC url used to validate anchor proofs")]
+    ethereum_rpc_url: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct ImportDaemonConfigOptions {
+    #[arg(help = "Existing ceramic daemon config file")]
+    file: PathBuf,
+    #[arg(
+        long,
+        help = "Wheel config file to write, format is chosen by extension. Defaults to ceramic.json in the working directory"
+    )]
+    output: Option<PathBuf>,
+    #[arg(long, help = "Overwrite an existing wheel config file")]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+struct LintOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(long, help = "Fail on warnings as well as errors")]
+    strict: bool,
+}
+
+#[derive(Parser, Debug)]
+struct ConfigFileOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct StopOptions {
+    #[arg(
+        long,
+        default_value_t = wheel_3box::DEFAULT_STOP_TIMEOUT.as_secs(),
+        help = "Seconds to wait for ceramic to exit before killing it"
+    )]
+    timeout: u64,
+}
+
+#[derive(Parser, Debug)]
+struct SupervisorArgs {
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Crashes allowed within the restart window before giving up"
+    )]
+    max_restarts: u32,
+    #[arg(
+        long,
+        default_value_t = 600,
+        help = "Seconds over which crashes are counted"
+    )]
+    restart_window: u64,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Seconds to wait before the first restart, doubling for each consecutive crash"
+    )]
+    initial_backoff: u64,
+    #[arg(
+        long,
+        default_value_t = 60,
+        help = "Maximum seconds to wait before a restart"
+    )]
+    max_backoff: u64,
+    #[arg(
+        long,
+        help = "Shell command run before each restart, with WHEEL_RESTART_COUNT, WHEEL_EXIT_REASON and WHEEL_EXIT_CODE set"
+    )]
+    on_restart: Option<String>,
+}
+
+impl From<SupervisorArgs> for wheel_3box::SupervisorOptions {
+    fn from(args: SupervisorArgs) -> Self {
+        Self {
+            max_restarts: args.max_restarts,
+            window: Duration::from_secs(args.restart_window),
+            initial_backoff: Duration::from_secs(args.initial_backoff),
+            max_backoff: Duration::from_secs(args.max_backoff),
+            on_restart: args.on_restart,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct StartOptions {
+    #[command(flatten)]
+    config: ConfigFileOptions,
+    #[arg(long, help = "Restart ceramic when it crashes")]
+    supervise: bool,
+    #[command(flatten)]
+    supervisor: SupervisorArgs,
+}
+
+impl StartOptions {
+    fn supervisor_options(self) -> Option<wheel_3box::SupervisorOptions> {
+        self.supervise.then(|| self.supervisor.into())
+    }
+}
+
+#[derive(Parser, Debug)]
+struct RestartOptions {
+    #[command(flatten)]
+    start: StartOptions,
+    #[command(flatten)]
+    stop: StopOptions,
+}
+
+#[derive(Parser, Debug)]
+struct SuperviseOptions {
+    #[command(flatten)]
+    config: ConfigFileOptions,
+    #[arg(long, hide = true)]
+    daemon_config: Option<PathBuf>,
+    #[command(flatten)]
+    supervisor: SupervisorArgs,
+}
+
+#[derive(Parser, Debug)]
+struct ConvertConfigOptions {
+    #[arg(help = "Wheel config file to convert")]
+    input: PathBuf,
+    #[arg(help = "File to write, format is chosen by extension (json, toml, yaml or yml)")]
+    output: PathBuf,
+    #[arg(long, help = "Overwrite an existing output file")]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+struct ConfigGetOptions {
+    #[command(flatten)]
+    config: ConfigFileOptions,
+    #[arg(help = "Dotted path of the setting, e.g. http_api.port")]
+    path: String,
+}
+
+#[derive(Parser, Debug)]
+struct ConfigChangeOptions {
+    #[command(flatten)]
+    config: ConfigFileOptions,
+    #[arg(long, help = "Restart a running ceramic without asking")]
+    restart: bool,
+    #[arg(
+        long,
+        conflicts_with = "restart",
+        help = "Leave a running ceramic on the previous config without asking"
+    )]
+    no_restart: bool,
+}
+
+impl ConfigChangeOptions {
+    fn restart(&self) -> Option<bool> {
+        match (self.restart, self.no_restart) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct ConfigSetOptions {
+    #[command(flatten)]
+    change: ConfigChangeOptions,
+    #[arg(help = "Dotted path of the setting, e.g. http_api.port")]
+    path: String,
+    #[arg(help = "Value to set, parsed as json where possible")]
+    value: String,
+}
+
+#[derive(Parser, Debug)]
+struct ConfigUnsetOptions {
+    #[command(flatten)]
+    change: ConfigChangeOptions,
+    #[arg(help = "Dotted path of the setting to reset to its default")]
+    path: String,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    #[command(about = "Show the project config, with secrets redacted")]
+    Show(ConfigFileOptions),
+    #[command(about = "Print a setting of the project config")]
+    Get(ConfigGetOptions),
+    #[command(about = "Change a setting and regenerate the daemon config")]
+    Set(ConfigSetOptions),
+    #[command(about = "Reset a setting to its default and regenerate the daemon config")]
+    Unset(ConfigUnsetOptions),
+    #[command(
+        about = "Edit the project config in $VISUAL or $EDITOR and regenerate the daemon config"
+    )]
+    Edit(ConfigChangeOptions),
+    #[command(about = "Show the effective config and where each value came from")]
+    Sources(ConfigFileOptions),
+    #[command(about = "Convert a wheel config between json, toml and yaml")]
+    Convert(ConvertConfigOptions),
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum EnvFormat {
+    Dotenv,
+    Shell,
+    Docker,
+}
+
+impl From<EnvFormat> for wheel_3box::EnvFormat {
+    fn from(format: EnvFormat) -> Self {
+        match format {
+            EnvFormat::Dotenv => Self::Dotenv,
+            EnvFormat::Shell => Self::Shell,
+            EnvFormat::Docker => Self::DockerEnvFile,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct ExportEnvOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = EnvFormat::Dotenv)]
+    format: EnvFormat,
+    #[arg(
+        long,
+        help = "Keep env: and file: secret references instead of resolving them"
+    )]
+    secret_references: bool,
+    #[arg(long, help = "File to write the variables to, defaults to stdout")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportCommand {
+    #[command(about = "Export the effective daemon config as CERAMIC_* environment variables")]
+    Env(ExportEnvOptions),
+}
+
+#[derive(Parser, Debug)]
+struct GenerateComposeOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Directory to write compose.yaml and its daemon config to, defaults to compose in the working directory"
+    )]
+    output: Option<PathBuf>,
+    #[arg(long, help = "Overwrite an existing compose file")]
+    force: bool,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum ConfigSection {
+    Ipfs,
+    StateStore,
+    HttpApi,
+    Network,
+    Anchor,
+    Node,
+    DidResolvers,
+    Indexing,
+}
+
+impl From<ConfigSection> for wheel_3box::ConfigSection {
+    fn from(section: ConfigSection) -> Self {
+        match section {
+            ConfigSection::Ipfs => Self::Ipfs,
+            ConfigSection::StateStore => Self::StateStore,
+            ConfigSection::HttpApi => Self::HttpApi,
+            ConfigSection::Network => Self::Network,
+            ConfigSection::Anchor => Self::Anchor,
+            ConfigSection::Node => Self::Node,
+            ConfigSection::DidResolvers => Self::DidResolvers,
+            ConfigSection::Indexing => Self::Indexing,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct ReconfigureOptions {
+    #[command(flatten)]
+    change: ConfigChangeOptions,
+    #[arg(value_enum, help = "Section to configure, defaults to all of them")]
+    section: Option<ConfigSection>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum ResourceProfile {
+    Small,
+    Medium,
+    Large,
+}
+
+impl From<ResourceProfile> for wheel_3box::ResourceProfile {
+    fn from(profile: ResourceProfile) -> Self {
+        match profile {
+            ResourceProfile::Small => Self::Small,
+            ResourceProfile::Medium => Self::Medium,
+            ResourceProfile::Large => Self::Large,
+        }
+    }
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum DaemonOutput {
+    None,
+    Errors,
+    All,
+}
+
+impl From<DaemonOutput> for wheel_3box::DaemonOutput {
+    fn from(output: DaemonOutput) -> Self {
+        match output {
+            DaemonOutput::None => Self::None,
+            DaemonOutput::Errors => Self::Errors,
+            DaemonOutput::All => Self::All,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct DoctorOptions {
+    #[command(flatten)]
+    config: ConfigFileOptions,
+    #[arg(long, help = "Print the results as json")]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+struct LogsOptions {
+    #[arg(long, short = 'f', help = "Keep printing new output")]
+    follow: bool,
+    #[arg(long, help = "Only print lines matching a regular expression")]
+    grep: Option<String>,
+    #[arg(
+        long,
+        help = "Only print lines since a duration ago, e.g. 10m or 2h, or a timestamp like 2024-01-01T12:00:00"
+    )]
+    since: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct GenerateKubernetesOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "File to write the manifests to, defaults to kubernetes.yaml in the working directory"
+    )]
+    output: Option<PathBuf>,
+    #[arg(long, default_value = "default", help = "Namespace for the manifests")]
+    namespace: String,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ResourceProfile::Medium,
+        help = "Cpu, memory and volume sizes for the ceramic node"
+    )]
+    profile: ResourceProfile,
+    #[arg(long, help = "Overwrite an existing manifest file")]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+struct GenerateSystemdOptions {
+    #[arg(
+        long,
+        help = "Wheel config file, defaults to ceramic.json, .toml or .yaml in the working directory"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Directory to write the units to, defaults to systemd in the working directory"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "User to run the services as, defaults to the current user"
+    )]
+    user: Option<String>,
+    #[arg(
+        long,
+        help = "Environment file for the ceramic service, defaults to ceramic.env in the working directory"
+    )]
+    env_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Demo application directory, to also generate a unit for the app"
+    )]
+    app_directory: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum GenerateCommand {
+    #[command(about = "Generate a docker compose stack for ceramic and the services it uses")]
+    Compose(GenerateComposeOptions),
+    #[command(about = "Generate kubernetes manifests for a ceramic node")]
+    Kubernetes(GenerateKubernetesOptions),
+    #[command(about = "Generate systemd units for ceramic and the demo application")]
+    Systemd(GenerateSystemdOptions),
+}
+
+#[derive(Parser, Debug)]
+struct SchemaOptions {
+    #[arg(
+        long,
+        help = "Generate the schema for daemon_config.json instead of the wheel config"
+    )]
+    daemon: bool,
+    #[arg(long, help = "File to write the schema to, defaults to stdout")]
+    output: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     #[command(about = "Non-interactive setup for ceramic and compose-db")]
     Quiet(QuietOptions),
+    #[command(about = "Create a wheel config from an existing ceramic daemon config")]
+    ImportDaemonConfig(ImportDaemonConfigOptions),
+    #[command(about = "Check a wheel config for production readiness")]
+    Lint(LintOptions),
+    #[command(about = "Start ceramic in the background for an existing project")]
+    Start(StartOptions),
+    #[command(about = "Stop ceramic started with wheel start")]
+    Stop(StopOptions),
+    #[command(about = "Stop and start ceramic")]
+    Restart(RestartOptions),
+    #[command(about = "Run ceramic in the foreground, restarting it when it crashes")]
+    Supervise(SuperviseOptions),
+    #[command(about = "Check the environment and project for problems")]
+    Doctor(DoctorOptions),
+    #[command(about = "Print ceramic's output captured by wheel")]
+    Logs(LogsOptions),
+    #[command(about = "Show whether ceramic is running")]
+    Status(ConfigFileOptions),
+    #[command(subcommand, about = "Inspect and manage the wheel config")]
+    Config(ConfigCommand),
+    #[command(about = "Change the advanced configuration of an existing project")]
+    Reconfigure(ReconfigureOptions),
+    #[command(about = "Generate JSON Schema for the wheel or daemon config")]
+    Schema(SchemaOptions),
+    #[command(subcommand, about = "Export the config for other tools")]
+    Export(ExportCommand),
+    #[command(subcommand, about = "Generate deployment files from the wheel config")]
+    Generate(GenerateCommand),
 }
 
 #[derive(Parser, Debug)]
@@ -90,12 +540,56 @@ struct ProgramArgs {
     composedb_version: Option<String>,
     #[arg(long)]
     template_branch: Option<String>,
+    #[arg(
+        long,
+        help = "File defining additional networks, defaults to networks.json, .toml or .yaml in the working directory"
+    )]
+    networks_file: Option<PathBuf>,
+    #[arg(
+        long = "set",
+        value_name = "PATH=VALUE",
+        value_parser = parse_config_override,
+        help = "Override a config value, e.g. http_api.port=7008. Takes precedence over CERAMIC_* and WHEEL_* environment variables"
+    )]
+    overrides: Vec<(String, String)>,
+    #[arg(
+        long,
+        default_value_t = wheel_3box::DEFAULT_READY_TIMEOUT.as_secs(),
+        help = "Seconds to wait for ceramic to become ready after starting it"
+    )]
+    ready_timeout: u64,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DaemonOutput::Errors,
+        help = "Ceramic output to echo when wheel runs it, all output is written to ceramic.log"
+    )]
+    daemon_output: DaemonOutput,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+fn parse_config_override(s: &str) -> anyhow::Result<(String, String)> {
+    let (path, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected PATH=VALUE, got {}", s))?;
+    Ok((path.to_string(), value.to_string()))
+}
+
 static CANCEL_REQUEST_CNT: AtomicUsize = AtomicUsize::new(0);
 
+/// Networks are only loaded by the commands that resolve them, so a broken networks file does not
+/// stop the other commands
+fn load_networks(
+    networks_file: Option<PathBuf>,
+    working_directory: &Path,
+) -> anyhow::Result<wheel_3box::NetworkRegistry> {
+    match networks_file {
+        Some(f) => wheel_3box::NetworkRegistry::load(f),
+        None => wheel_3box::NetworkRegistry::find(working_directory),
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let _ = env_logger::builder()
@@ -126,6 +620,33 @@ async fn main() -> anyhow::Result<()> {
     if let Some(v) = args.template_branch {
         versions.template_branch = Some(v);
     }
+    let networks_file = args.networks_file;
+    let ready_timeout = Duration::from_secs(args.ready_timeout);
+    let daemon = wheel_3box::DaemonOptions {
+        ready_timeout,
+        output: args.daemon_output.into(),
+    };
+    let layers = args.overrides.into_iter().fold(
+        wheel_3box::ConfigLayers::from_env(),
+        |layers, (path, value)| layers.with_flag(path, value),
+    );
+
+    // the supervisor handles ctrl-c itself, and must outlive it to stop ceramic
+    if let Some(Commands::Supervise(s)) = args.command {
+        let config = s
+            .config
+            .config
+            .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+        return wheel_3box::supervise(
+            &working_directory,
+            &config,
+            &layers,
+            s.daemon_config,
+            s.supervisor.into(),
+            daemon.output,
+        )
+        .await;
+    }
 
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel::<()>(8);
 
@@ -134,8 +655,9 @@ async fn main() -> anyhow::Result<()> {
             None => {
                 log::info!("Starting wheel interactive configuration");
 
+                let networks = load_networks(networks_file, &working_directory)?;
                 tokio::select! {
-                    res = wheel_3box::interactive_default(working_directory, versions) => {
+                    res = wheel_3box::interactive_default(working_directory, versions, layers, networks, daemon) => {
                         res?
                     },
                     _shutdown = shutdown_rx.recv() => {
@@ -145,13 +667,14 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
             Some(Commands::Quiet(q)) => {
-                let network = match q.network {
-                    Network::InMemory => wheel_3box::NetworkIdentifier::InMemory,
-                    Network::Local => wheel_3box::NetworkIdentifier::Local,
-                    Network::Dev => wheel_3box::NetworkIdentifier::Dev,
-                    Network::Clay => wheel_3box::NetworkIdentifier::Clay,
-                    Network::Mainnet => wheel_3box::NetworkIdentifier::Mainnet,
-                };
+                let networks = load_networks(networks_file, &working_directory)?;
+                let network = networks.get(&q.network).cloned().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown network {}, expected one of {}",
+                        q.network,
+                        networks.names().join(", ")
+                    )
+                })?;
                 let with_composedb = q.setup == Setup::ComposeDB;
                 let with_app_template = q.setup == Setup::DemoApplication;
                 let did = if let DidCommand::Specify(opts) = q.did {
@@ -166,7 +689,7 @@ async fn main() -> anyhow::Result<()> {
                 let opts = wheel_3box::QuietOptions {
                     project_name: q.project_name,
                     working_directory: working_directory,
-                    network_identifier: network,
+                    network,
                     versions,
                     did,
                     with_ceramic: with_app_template
@@ -174,11 +697,22 @@ async fn main() -> anyhow::Result<()> {
                         || q.setup == Setup::CeramicOnly,
                     with_composedb: with_app_template || with_composedb,
                     with_app_template: with_app_template,
+                    layers,
+                    cas_url: q.cas_url,
+                    cas_auth_method: q.cas_auth_method,
+                    ethereum_rpc_url: q.ethereum_rpc_url,
+                    daemon,
                 };
 
                 tokio::select! {
                     res = wheel_3box::quiet(opts)  => {
-                        res?
+                        match res {
+                            Err(e) if e.is::<wheel_3box::ReadinessReport>() => {
+                                log::error!("{}", e);
+                                std::process::exit(wheel_3box::NOT_READY_EXIT_CODE);
+                            }
+                            res => res?,
+                        }
                     },
                     _shutdown = shutdown_rx.recv() => {
                         log::info!("\nReceived shutdown request, exiting quiet setup");
@@ -186,9 +720,262 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
             }
+            Some(Commands::ImportDaemonConfig(i)) => {
+                let output = i
+                    .output
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::import_daemon_config(&i.file, &output, i.force).await?;
+                return Ok(());
+            }
+            Some(Commands::Lint(l)) => {
+                let config = l
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::lint(&config, &layers, l.strict).await?;
+                return Ok(());
+            }
+            Some(Commands::Start(s)) => {
+                let config = s
+                    .config
+                    .config
+                    .clone()
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                let supervise = s.supervisor_options();
+                wheel_3box::start(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    supervise.as_ref(),
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Stop(s)) => {
+                wheel_3box::stop(&working_directory, Duration::from_secs(s.timeout)).await?;
+                return Ok(());
+            }
+            Some(Commands::Restart(r)) => {
+                let config = r
+                    .start
+                    .config
+                    .config
+                    .clone()
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                let supervise = r.start.supervisor_options();
+                wheel_3box::restart(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    Duration::from_secs(r.stop.timeout),
+                    supervise.as_ref(),
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Supervise(_)) => unreachable!("supervise runs outside the main task"),
+            Some(Commands::Doctor(d)) => {
+                let config = d
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::doctor(
+                    &working_directory,
+                    &config,
+                    networks_file.as_deref(),
+                    &layers,
+                    d.json,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Logs(l)) => {
+                let filter = wheel_3box::LogFilter::new(l.grep.as_deref(), l.since.as_deref())?;
+                wheel_3box::logs(&working_directory, &filter, l.follow).await?;
+                return Ok(());
+            }
+            Some(Commands::Status(c)) => {
+                let config = c
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::status(&working_directory, &config, &layers).await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Show(c))) => {
+                let config = c
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_show(&config).await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Get(g))) => {
+                let config = g
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_get(&config, &g.path).await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Set(c))) => {
+                let restart = c.change.restart();
+                let config = c
+                    .change
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_set(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    &c.path,
+                    &c.value,
+                    restart,
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Unset(c))) => {
+                let restart = c.change.restart();
+                let config = c
+                    .change
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_unset(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    &c.path,
+                    restart,
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Edit(c))) => {
+                let restart = c.restart();
+                let config = c
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_edit(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    restart,
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Reconfigure(r)) => {
+                let restart = r.change.restart();
+                let config = r
+                    .change
+                    .config
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::reconfigure(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    r.section.map(Into::into),
+                    restart,
+                    ready_timeout,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Sources(c))) => {
+                let config = c
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::config_sources(&config, &layers).await?;
+                return Ok(());
+            }
+            Some(Commands::Config(ConfigCommand::Convert(c))) => {
+                wheel_3box::convert_config(&c.input, &c.output, c.force).await?;
+                return Ok(());
+            }
+            Some(Commands::Schema(s)) => {
+                wheel_3box::schema(s.daemon, s.output).await?;
+                return Ok(());
+            }
+            Some(Commands::Generate(GenerateCommand::Compose(g))) => {
+                let config = g
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                let output = g
+                    .output
+                    .unwrap_or_else(|| working_directory.join("compose"));
+                wheel_3box::generate_compose(
+                    &config,
+                    &layers,
+                    &output,
+                    versions.ceramic.as_ref(),
+                    g.force,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Generate(GenerateCommand::Kubernetes(g))) => {
+                let config = g
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                let output = g
+                    .output
+                    .unwrap_or_else(|| working_directory.join("kubernetes.yaml"));
+                wheel_3box::generate_kubernetes(
+                    &config,
+                    &layers,
+                    &output,
+                    &g.namespace,
+                    g.profile.into(),
+                    versions.ceramic.as_ref(),
+                    g.force,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Generate(GenerateCommand::Systemd(g))) => {
+                let config = g
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                let output = g
+                    .output
+                    .unwrap_or_else(|| working_directory.join("systemd"));
+                let user = g.user.or_else(|| std::env::var("USER").ok());
+                wheel_3box::generate_systemd(
+                    &working_directory,
+                    &config,
+                    &layers,
+                    &output,
+                    user,
+                    g.env_file,
+                    g.app_directory,
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Commands::Export(ExportCommand::Env(e))) => {
+                let config = e
+                    .config
+                    .unwrap_or_else(|| wheel_3box::find_config_file(&working_directory));
+                wheel_3box::export_env(
+                    &config,
+                    &layers,
+                    e.format.into(),
+                    e.secret_references,
+                    e.output,
+                )
+                .await?;
+                return Ok(());
+            }
         };
 
-        log::info!("Wheel setup is complete. If running a clay or mainnet node, please check out https://github.com/ceramicstudio/simpledeploy to deploy with k8s.");
+        log::info!("Wheel setup is complete. If running a clay or mainnet node, use `wheel generate kubernetes` to deploy with k8s.");
 
         if let Some(child) = opt_child {
             log::info!("Ceramic is now running in the background. Please use another terminal for additional commands. You can interrupt ceramic using ctrl-c.");
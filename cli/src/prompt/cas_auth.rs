@@ -1,5 +1,5 @@
 use crate::did::DidAndPrivateKey;
-use ceramic_config::{CasAuth, NetworkIdentifier};
+use ceramic_config::{CasAuth, NetworkDefinition};
 use inquire::{Select, Text};
 use serde::Deserialize;
 
@@ -32,18 +32,10 @@ impl std::fmt::Display for CasSelect {
 
 pub async fn prompt(
     doc: &DidAndPrivateKey,
-    id: &NetworkIdentifier,
+    network: &NetworkDefinition,
 ) -> anyhow::Result<Option<CasAuth>> {
-    let url = match id {
-        NetworkIdentifier::InMemory => None,
-        NetworkIdentifier::Local | NetworkIdentifier::Dev => {
-            Some("https://cas-qa.3boxlabs.com".to_string())
-        }
-        NetworkIdentifier::Clay => Some("https://cas-clay.3boxlabs.com".to_string()),
-        NetworkIdentifier::Mainnet => Some("https://cas.3boxlabs.com".to_string()),
-    };
-    if let Some(url) = url {
-        let url = Text::new("CAS Url").with_default(&url).prompt()?;
+    if let Some(url) = &network.cas_url {
+        let url = Text::new("CAS Url").with_default(url).prompt()?;
         let pk = match Select::new(
             "CAS Authentication",
             vec![
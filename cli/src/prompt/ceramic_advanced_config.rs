@@ -22,6 +22,49 @@ impl std::fmt::Display for ConfigSelect {
     }
 }
 
+/// Part of the config that can be configured on its own
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigSection {
+    Ipfs,
+    StateStore,
+    HttpApi,
+    Network,
+    Anchor,
+    Node,
+    DidResolvers,
+    Indexing,
+}
+
+impl ConfigSection {
+    pub fn all() -> &'static [ConfigSection] {
+        &[
+            Self::Ipfs,
+            Self::StateStore,
+            Self::HttpApi,
+            Self::Network,
+            Self::Anchor,
+            Self::Node,
+            Self::DidResolvers,
+            Self::Indexing,
+        ]
+    }
+}
+
+impl std::fmt::Display for ConfigSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ipfs => write!(f, "IPFS"),
+            Self::StateStore => write!(f, "State Store"),
+            Self::HttpApi => write!(f, "HTTP API"),
+            Self::Network => write!(f, "Network"),
+            Self::Anchor => write!(f, "Anchor Service"),
+            Self::Node => write!(f, "Node"),
+            Self::DidResolvers => write!(f, "DID Resolvers"),
+            Self::Indexing => write!(f, "Indexing"),
+        }
+    }
+}
+
 pub async fn prompt(
     working_directory: impl AsRef<Path>,
     cfg: &mut Config,
@@ -35,7 +78,7 @@ pub async fn prompt(
 
     match ans {
         ConfigSelect::Defaults => {
-            log::info!("Using default configuration for {}", cfg.network.id);
+            log::info!("Using default configuration for {}", cfg.network);
         }
         ConfigSelect::Advanced => {
             configure(cfg, admin_did, working_directory).await?;
@@ -46,17 +89,63 @@ pub async fn prompt(
 }
 
 pub fn configure_ipfs(cfg: &mut Config) -> anyhow::Result<()> {
+    let default = if let Ipfs::Bundled = cfg.ipfs { 1 } else { 0 };
     let ans = Select::new(
-        "Bundled or Remote IPFS (default=Remote)",
+        "Bundled or Remote IPFS",
         vec![Ipfs::Remote(IpfsRemote::default()), Ipfs::Bundled],
     )
+    .with_starting_cursor(default)
     .prompt()?;
 
     let r = if let Ipfs::Remote(_) = ans {
-        let ipfs = IpfsRemote {
-            host: Text::new("IPFS Hostname").prompt()?,
+        let current = if let Ipfs::Remote(r) = &cfg.ipfs {
+            r.clone()
+        } else {
+            IpfsRemote::default()
         };
-        Ipfs::Remote(ipfs)
+        let host = Text::new("IPFS Hostname")
+            .with_default(&current.host)
+            .prompt()?;
+        let pinning_endpoints = Text::new("Pinning endpoints (comma separated)")
+            .with_help_message("Additional pinning services, e.g. ipfs+context")
+            .with_default(&current.pinning_endpoints.join(","))
+            .prompt()?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let disable_peer_data_sync = Confirm::new("Disable peer data sync?")
+            .with_help_message("Stops the IPFS node from syncing data with other ceramic peers")
+            .with_default(current.disable_peer_data_sync)
+            .prompt()?;
+        let timeout = Text::new("IPFS request timeout in milliseconds (optional)")
+            .with_default(
+                &current
+                    .request_timeout_ms
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+            )
+            .with_validator(|s: &str| {
+                if s.trim().is_empty() || s.trim().parse::<u64>().is_ok() {
+                    Ok(validator::Validation::Valid)
+                } else {
+                    Ok(validator::Validation::Invalid(
+                        "Timeout must be a number of milliseconds".into(),
+                    ))
+                }
+            })
+            .prompt()?;
+        let request_timeout_ms = if timeout.trim().is_empty() {
+            None
+        } else {
+            Some(timeout.trim().parse()?)
+        };
+        Ipfs::Remote(IpfsRemote {
+            host,
+            pinning_endpoints,
+            disable_peer_data_sync,
+            request_timeout_ms,
+        })
     } else {
         Ipfs::Bundled
     };
@@ -82,14 +171,23 @@ pub async fn configure_state_store(
     cfg: &mut Config,
     working_directory: impl AsRef<Path>,
 ) -> anyhow::Result<()> {
+    let default = if let StateStore::S3(_) = cfg.state_store {
+        1
+    } else {
+        0
+    };
     let ans = Select::new(
-        "State Store (default=local)",
+        "State Store",
         vec![StateStoreSelect::Local, StateStoreSelect::S3],
     )
+    .with_starting_cursor(default)
     .prompt()?;
 
     let r = if let StateStoreSelect::Local = ans {
-        let default = working_directory.as_ref().join("ceramic-state");
+        let default = match &cfg.state_store {
+            StateStore::LocalDirectory(p) => p.clone(),
+            _ => working_directory.as_ref().join("ceramic-state"),
+        };
         let location = Text::new("Directory")
             .with_default(&default.display().to_string())
             .prompt()?;
@@ -99,8 +197,17 @@ pub async fn configure_state_store(
         }
         StateStore::LocalDirectory(location)
     } else {
-        let bucket = Text::new("Bucket").with_default("ceramic").prompt()?;
-        let endpoint = Text::new("Endpoint").prompt()?;
+        let current = match &cfg.state_store {
+            StateStore::S3(s3) => s3.clone(),
+            _ => S3StateStore {
+                bucket: "ceramic".to_string(),
+                endpoint: String::default(),
+            },
+        };
+        let bucket = Text::new("Bucket").with_default(&current.bucket).prompt()?;
+        let endpoint = Text::new("Endpoint")
+            .with_default(&current.endpoint)
+            .prompt()?;
         StateStore::S3(S3StateStore {
             bucket: bucket,
             endpoint: endpoint,
@@ -110,7 +217,11 @@ pub async fn configure_state_store(
     Ok(())
 }
 
-pub fn configure_http_api(cfg: &mut Config, admin_did: &DidAndPrivateKey) -> anyhow::Result<()> {
+/// Configure the http api, adding the admin DID to the existing admin DIDs if given
+pub fn configure_http_api(
+    cfg: &mut Config,
+    admin_did: Option<&DidAndPrivateKey>,
+) -> anyhow::Result<()> {
     cfg.http_api.hostname = Text::new("Bind address")
         .with_default(&cfg.http_api.hostname)
         .prompt()?;
@@ -121,9 +232,26 @@ pub fn configure_http_api(cfg: &mut Config, admin_did: &DidAndPrivateKey) -> any
     let cors = Text::new("Cors origins, comma separated")
         .with_default(&cfg.http_api.cors_allowed_origins.join(","))
         .prompt()?;
-    let cors = cors.split(",").map(|s| s.trim().to_string()).collect();
+    let cors = cors
+        .split(",")
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
     cfg.http_api.cors_allowed_origins = cors;
-    cfg.http_api.admin_dids = vec![admin_did.did().to_string()];
+    if let Some(admin_did) = admin_did {
+        if !cfg.http_api.admin_dids.iter().any(|d| d == admin_did.did()) {
+            cfg.http_api.admin_dids.push(admin_did.did().to_string());
+        }
+    }
+    let admin_dids = Text::new("Admin DIDs, comma separated")
+        .with_help_message("DIDs allowed to use the admin api, e.g. to index models")
+        .with_default(&cfg.http_api.admin_dids.join(","))
+        .prompt()?;
+    cfg.http_api.admin_dids = admin_dids
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
     Ok(())
 }
 
@@ -146,12 +274,171 @@ fn configure_network(cfg: &mut Config) -> anyhow::Result<()> {
     Ok(())
 }
 
+enum AnchorSelect {
+    Current,
+    SelfHosted,
+}
+
+impl std::fmt::Display for AnchorSelect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Current => write!(f, "Network CAS (configured during setup)"),
+            Self::SelfHosted => write!(f, "Self-hosted CAS"),
+        }
+    }
+}
+
+/// Configure anchoring. A self-hosted CAS using DID auth uses the admin DID if given, otherwise
+/// the seed already configured.
+fn configure_anchor(cfg: &mut Config, admin_did: Option<&DidAndPrivateKey>) -> anyhow::Result<()> {
+    if cfg.network.id == NetworkIdentifier::InMemory {
+        return Ok(());
+    }
+    let default = if let Anchor::SelfHosted { .. } = cfg.anchor {
+        1
+    } else {
+        0
+    };
+    let ans = Select::new(
+        "Anchor Service",
+        vec![AnchorSelect::Current, AnchorSelect::SelfHosted],
+    )
+    .with_help_message("Private networks can anchor with their own CAS")
+    .with_starting_cursor(default)
+    .prompt()?;
+    if let AnchorSelect::SelfHosted = ans {
+        let url = Text::new("Self-hosted CAS Url")
+            .with_default(cfg.anchor.url().unwrap_or("http://localhost:8081"))
+            .prompt()?;
+        let current = if let Anchor::SelfHosted { auth_method, .. } = &cfg.anchor {
+            auth_method.clone().unwrap_or_default()
+        } else {
+            "did".to_string()
+        };
+        let auth_method = Text::new("CAS Authentication Method")
+            .with_help_message("Leave empty if the CAS does not authenticate requests")
+            .with_default(&current)
+            .prompt()?;
+        let auth_method = if auth_method.trim().is_empty() {
+            None
+        } else {
+            Some(auth_method.trim().to_string())
+        };
+        let private_seed_url = if auth_method.as_deref() == Some("did") {
+            // without an admin DID, e.g. when reconfiguring, keep the seed already in the config
+            let current = match &cfg.anchor {
+                Anchor::SelfHosted {
+                    private_seed_url, ..
+                } => private_seed_url.clone(),
+                Anchor::RemoteDid {
+                    private_seed_url, ..
+                } => Some(private_seed_url.clone()),
+                _ => None,
+            };
+            match (admin_did, current) {
+                (Some(admin_did), _) => Some(Secret::new(admin_did.cas_auth())),
+                (None, Some(current)) => Some(current),
+                (None, None) => anyhow::bail!(
+                    "DID authentication needs an admin DID, please run wheel setup to create one"
+                ),
+            }
+        } else {
+            None
+        };
+        cfg.anchor = Anchor::SelfHosted {
+            url,
+            auth_method,
+            private_seed_url,
+        };
+    } else if let Anchor::SelfHosted {
+        private_seed_url, ..
+    } = &cfg.anchor
+    {
+        // switching back from a self-hosted CAS, so use the network's CAS with DID auth when
+        // there is a seed to authenticate with
+        let url = cfg
+            .network
+            .cas_url()
+            .ok_or_else(|| anyhow::anyhow!("Network {} has no anchor service", cfg.network))?;
+        let private_seed_url = admin_did
+            .map(|d| Secret::new(d.cas_auth()))
+            .or_else(|| private_seed_url.clone());
+        cfg.anchor = match private_seed_url {
+            Some(private_seed_url) => Anchor::RemoteDid {
+                url,
+                private_seed_url,
+            },
+            None => Anchor::Ip { url },
+        };
+    }
+    let rpc = Text::new("Ethereum RPC Url (optional)")
+        .with_help_message(
+            "Used to validate anchor proofs, e.g. https://mainnet.infura.io/v3/<project id>",
+        )
+        .with_default(cfg.ethereum_rpc_url.as_deref().unwrap_or_default())
+        .prompt()?;
+    cfg.ethereum_rpc_url = if rpc.trim().is_empty() {
+        None
+    } else {
+        Some(rpc.trim().to_string())
+    };
+    Ok(())
+}
+
 pub fn configure_node(cfg: &mut Config) -> anyhow::Result<()> {
     let gateway = Confirm::new("Run as gateway?")
         .with_help_message("Gateway nodes cannot perform mutations")
-        .with_default(false)
+        .with_default(cfg.node.gateway)
         .prompt()?;
     cfg.node.gateway = gateway;
+    cfg.node.stream_cache_limit = Text::new("Stream cache size")
+        .with_help_message("Maximum number of streams kept in memory")
+        .with_default(&cfg.node.stream_cache_limit.to_string())
+        .with_validator(|s: &str| {
+            if s.trim().parse::<usize>().is_ok() {
+                Ok(validator::Validation::Valid)
+            } else {
+                Ok(validator::Validation::Invalid(
+                    "Stream cache size must be a number".into(),
+                ))
+            }
+        })
+        .prompt()?
+        .trim()
+        .parse()?;
+    cfg.node.sync_override = Confirm::new("Always sync streams from the network?")
+        .with_help_message(
+            "Loads streams from the network on every request instead of preferring cached state",
+        )
+        .with_default(cfg.node.sync_override)
+        .prompt()?;
+    Ok(())
+}
+
+pub fn configure_did_resolvers(cfg: &mut Config) -> anyhow::Result<()> {
+    let mut networks = cfg.did_resolvers.ethr_networks();
+    for network in &networks {
+        log::info!(
+            "Using ethr DID resolver network {} at {}",
+            network.name,
+            network.rpc_url
+        );
+    }
+    while Confirm::new("Add an ethr DID resolver network?")
+        .with_help_message("Required to resolve did:ethr DIDs, for example did:ethr:mainnet")
+        .with_default(false)
+        .prompt()?
+    {
+        let name = Text::new("Ethereum network name")
+            .with_default("mainnet")
+            .prompt()?;
+        let rpc_url = Text::new("Ethereum RPC Url")
+            .with_help_message("Example: https://mainnet.infura.io/v3/<project id>")
+            .prompt()?;
+        networks.retain(|n| n.name != name);
+        networks.push(EthrNetwork { name, rpc_url });
+    }
+    cfg.did_resolvers.set_ethr_networks(networks);
     Ok(())
 }
 
@@ -183,16 +470,108 @@ impl std::fmt::Display for SqliteSelect {
     }
 }
 
+fn required(s: &str) -> Result<validator::Validation, CustomUserError> {
+    if s.trim().is_empty() {
+        Ok(validator::Validation::Invalid("Value is required".into()))
+    } else {
+        Ok(validator::Validation::Valid)
+    }
+}
+
+fn configure_postgres(current: PostgresDb) -> anyhow::Result<PostgresDb> {
+    let host = Text::new("Postgres Host")
+        .with_default(&current.host)
+        .with_validator(required)
+        .prompt()?;
+    let port = Text::new("Postgres Port")
+        .with_default(&current.port.to_string())
+        .with_validator(|s: &str| match s.trim().parse::<u16>() {
+            Ok(p) if p > 0 => Ok(validator::Validation::Valid),
+            _ => Ok(validator::Validation::Invalid(
+                "Port must be a number between 1 and 65535".into(),
+            )),
+        })
+        .prompt()?
+        .trim()
+        .parse()?;
+    let user = Text::new("Postgres User")
+        .with_default(&current.user)
+        .with_validator(required)
+        .prompt()?;
+    // literal passwords are not shown, leaving the prompt empty keeps them
+    let password_default = current
+        .password
+        .as_ref()
+        .filter(|p| !p.is_literal())
+        .map(|p| p.reference().to_string())
+        .unwrap_or_default();
+    let password = Text::new("Postgres Password")
+        .with_help_message("Use env:NAME or file:/path to keep it out of the config")
+        .with_default(&password_default)
+        .prompt()?;
+    let password = if password.is_empty() {
+        current.password.filter(Secret::is_literal)
+    } else {
+        Some(Secret::new(password))
+    };
+    let database = Text::new("Postgres Database")
+        .with_default(&current.database)
+        .with_validator(required)
+        .prompt()?;
+
+    let mut ssl_modes = vec!["default".to_string()];
+    ssl_modes.extend(PostgresSslMode::all().iter().map(|m| m.to_string()));
+    let ssl_cursor = current
+        .ssl_mode
+        .and_then(|c| PostgresSslMode::all().iter().position(|m| *m == c))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let ssl_mode = Select::new("Postgres SSL Mode", ssl_modes)
+        .with_starting_cursor(ssl_cursor)
+        .prompt()?;
+    let ssl_mode = if ssl_mode == "default" {
+        None
+    } else {
+        Some(ssl_mode.parse()?)
+    };
+
+    let pool_size = Text::new("Postgres Connection Pool Size")
+        .with_help_message("Leave empty to use the daemon default")
+        .with_default(&current.pool_size.map(|s| s.to_string()).unwrap_or_default())
+        .with_validator(|s: &str| {
+            if s.trim().is_empty() || s.trim().parse::<u32>().map(|s| s > 0).unwrap_or(false) {
+                Ok(validator::Validation::Valid)
+            } else {
+                Ok(validator::Validation::Invalid(
+                    "Pool size must be a positive number".into(),
+                ))
+            }
+        })
+        .prompt()?;
+    let pool_size = if pool_size.trim().is_empty() {
+        None
+    } else {
+        Some(pool_size.trim().parse()?)
+    };
+
+    Ok(PostgresDb {
+        host: host.trim().to_string(),
+        port,
+        user: user.trim().to_string(),
+        password,
+        database: database.trim().to_string(),
+        ssl_mode,
+        pool_size,
+        extra_params: current.extra_params,
+    })
+}
+
 pub fn configure_indexing(
     cfg: &mut Config,
     working_directory: impl AsRef<Path>,
 ) -> anyhow::Result<()> {
-    let default = if cfg.indexing.db.contains("sqlite") {
-        0
-    } else {
-        1
-    };
-    let indexing = if cfg.network.id == NetworkIdentifier::Mainnet {
+    let default = if cfg.indexing.is_sqlite() { 0 } else { 1 };
+    let indexing = if !cfg.allows_sqlite() {
         IndexingSelect::Postgres
     } else {
         Select::new(
@@ -205,8 +584,13 @@ pub fn configure_indexing(
     match indexing {
         IndexingSelect::Sqlite => {
             if !cfg.allows_sqlite() {
-                anyhow::bail!("sqlite not allowed in environment {}", cfg.network.id);
+                anyhow::bail!("sqlite not allowed in environment {}", cfg.network);
             }
+            let current_directory = working_directory.as_ref().join("ceramic.db");
+            let location = match &cfg.indexing.db {
+                IndexingDb::Sqlite(p) if *p != current_directory => 1,
+                _ => 0,
+            };
             let ans = Select::new(
                 "Sqlite Database Location",
                 vec![
@@ -214,56 +598,72 @@ pub fn configure_indexing(
                     SqliteSelect::CustomDirectory,
                 ],
             )
-            .with_starting_cursor(default)
+            .with_starting_cursor(location)
             .prompt()?;
             match ans {
                 SqliteSelect::CurrentDirectory => {
-                    cfg.indexing.db = format!(
-                        "sqlite://{}/ceramic.db",
-                        working_directory.as_ref().display()
-                    );
+                    cfg.indexing.db = IndexingDb::Sqlite(current_directory);
                 }
                 SqliteSelect::CustomDirectory => {
-                    let default = if cfg.indexing.is_sqlite() {
-                        cfg.indexing.db.clone()
-                    } else {
-                        format!("{}/ceramic.db", working_directory.as_ref().display())
+                    let default = match &cfg.indexing.db {
+                        IndexingDb::Sqlite(p) => p.display().to_string(),
+                        _ => format!("{}/ceramic.db", working_directory.as_ref().display()),
                     };
                     let location = Text::new("Sqlite Database Location")
-                        .with_help_message("Example: sqlite:///directory-for-ceramic/ceramic.db")
+                        .with_help_message("Example: /directory-for-ceramic/ceramic.db")
                         .with_default(&default)
+                        .with_validator(|s: &str| {
+                            if s.trim().is_empty() {
+                                Ok(validator::Validation::Invalid(
+                                    "Location is required".into(),
+                                ))
+                            } else {
+                                Ok(validator::Validation::Valid)
+                            }
+                        })
                         .prompt()?;
-                    cfg.indexing.db = format!("sqlite://{}", location);
+                    cfg.indexing.db = IndexingDb::Sqlite(PathBuf::from(location.trim()));
                 }
             }
         }
         IndexingSelect::Postgres => {
-            let default = if !cfg.indexing.is_sqlite() {
-                cfg.indexing.db.clone()
-            } else {
-                Indexing::postgres_default().to_string()
+            let current = match &cfg.indexing.db {
+                IndexingDb::Postgres(pg) => pg.clone(),
+                _ => PostgresDb::default(),
             };
-            cfg.indexing.db = Text::new("Postgres Database Connection String")
-                .with_help_message(&format!("Example: {}", Indexing::postgres_default()))
-                .with_default(&default)
-                .prompt()?;
+            cfg.indexing.db = IndexingDb::Postgres(configure_postgres(current)?);
         }
     }
 
     Ok(())
 }
 
+/// Configure a single section, with prompts defaulting to the current config
+pub async fn configure_section(
+    cfg: &mut Config,
+    section: ConfigSection,
+    admin_did: Option<&DidAndPrivateKey>,
+    working_directory: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    match section {
+        ConfigSection::Ipfs => configure_ipfs(cfg),
+        ConfigSection::StateStore => configure_state_store(cfg, working_directory).await,
+        ConfigSection::HttpApi => configure_http_api(cfg, admin_did),
+        ConfigSection::Network => configure_network(cfg),
+        ConfigSection::Anchor => configure_anchor(cfg, admin_did),
+        ConfigSection::Node => configure_node(cfg),
+        ConfigSection::DidResolvers => configure_did_resolvers(cfg),
+        ConfigSection::Indexing => configure_indexing(cfg, working_directory),
+    }
+}
+
 pub async fn configure(
     cfg: &mut Config,
     admin_did: &DidAndPrivateKey,
     working_directory: impl AsRef<Path>,
 ) -> anyhow::Result<()> {
-    configure_ipfs(cfg)?;
-    configure_state_store(cfg, working_directory.as_ref()).await?;
-    configure_http_api(cfg, admin_did)?;
-    configure_network(cfg)?;
-    configure_node(cfg)?;
-    configure_indexing(cfg, working_directory)?;
-
+    for section in ConfigSection::all() {
+        configure_section(cfg, *section, Some(admin_did), working_directory.as_ref()).await?;
+    }
     Ok(())
 }
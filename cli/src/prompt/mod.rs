@@ -3,4 +3,4 @@ pub mod ceramic_advanced_config;
 pub mod did;
 pub mod project;
 
-pub use ceramic_advanced_config::{configure as advanced_config, prompt};
+pub use ceramic_advanced_config::{configure as advanced_config, prompt, ConfigSection};
@@ -0,0 +1,297 @@
+use crate::install::ceramic_daemon::health_check_url;
+use ceramic_config::{Config, Ipfs};
+use spinners::{Spinner, Spinners};
+use sqlx::Connection;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::mpsc::Receiver;
+
+/// Default time to wait for ceramic to become ready
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(180);
+/// Exit code used in quiet mode when ceramic does not become ready
+pub const NOT_READY_EXIT_CODE: i32 = 3;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const OUTPUT_LINES: usize = 20;
+const OUTPUT_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Checks run in order while waiting for ceramic to become ready
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadinessStage {
+    ProcessAlive,
+    PortBound,
+    Healthcheck,
+    Ipfs,
+    Database,
+}
+
+impl std::fmt::Display for ReadinessStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ProcessAlive => write!(f, "process alive"),
+            Self::PortBound => write!(f, "port bound"),
+            Self::Healthcheck => write!(f, "healthcheck"),
+            Self::Ipfs => write!(f, "ipfs reachable"),
+            Self::Database => write!(f, "database reachable"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CheckResult {
+    Passed,
+    Failed(String),
+    Skipped(String),
+    NotRun,
+}
+
+impl std::fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Passed => write!(f, "ok"),
+            Self::Failed(e) => write!(f, "failed: {}", e),
+            Self::Skipped(reason) => write!(f, "skipped, {}", reason),
+            Self::NotRun => write!(f, "not run"),
+        }
+    }
+}
+
+/// Why ceramic did not become ready, returned as the error from [`wait_until_ready`]
+#[derive(Debug)]
+pub struct ReadinessReport {
+    pub failed: ReadinessStage,
+    pub elapsed: Duration,
+    pub checks: Vec<(ReadinessStage, CheckResult)>,
+    pub output_file: PathBuf,
+    /// Last lines ceramic wrote to the output file
+    pub output: Vec<String>,
+}
+
+impl std::fmt::Display for ReadinessReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Ceramic was not ready after {}s, failed at {}",
+            self.elapsed.as_secs(),
+            self.failed
+        )?;
+        for (stage, result) in &self.checks {
+            writeln!(f, "    - {}: {}", stage, result)?;
+        }
+        if self.output.is_empty() {
+            write!(f, "No output in {}", self.output_file.display())
+        } else {
+            writeln!(f, "Last output from {}:", self.output_file.display())?;
+            for line in &self.output {
+                writeln!(f, "    {}", line)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl std::error::Error for ReadinessReport {}
+
+/// Last lines of a file, reading at most the end of the file
+pub async fn tail(file: &Path, lines: usize) -> anyhow::Result<Vec<String>> {
+    if !tokio::fs::try_exists(file).await? {
+        return Ok(vec![]);
+    }
+    let mut f = tokio::fs::File::open(file).await?;
+    let len = f.metadata().await?.len();
+    let start = len.saturating_sub(OUTPUT_TAIL_BYTES);
+    f.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut buf = vec![];
+    f.read_to_end(&mut buf).await?;
+    let contents = String::from_utf8_lossy(&buf);
+    let mut all: Vec<_> = contents.lines().collect();
+    if start > 0 && !all.is_empty() {
+        // the first line is likely partial
+        all.remove(0);
+    }
+    Ok(all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect())
+}
+
+async fn check_port(cfg: &Config) -> CheckResult {
+    let addr = (cfg.http_api.hostname.as_str(), cfg.http_api.port);
+    match tokio::time::timeout(CHECK_TIMEOUT, tokio::net::TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => CheckResult::Passed,
+        Ok(Err(e)) => {
+            CheckResult::Failed(format!("nothing listening on {}:{}, {}", addr.0, addr.1, e))
+        }
+        Err(_) => CheckResult::Failed(format!("timed out connecting to {}:{}", addr.0, addr.1)),
+    }
+}
+
+async fn check_healthcheck(client: &reqwest::Client, cfg: &Config) -> CheckResult {
+    let url = health_check_url(cfg);
+    match client.get(&url).send().await {
+        Ok(r) if r.status().is_success() => CheckResult::Passed,
+        Ok(r) => CheckResult::Failed(format!("{} responded with {}", url, r.status())),
+        Err(e) => CheckResult::Failed(e.to_string()),
+    }
+}
+
+pub(crate) async fn check_ipfs(client: &reqwest::Client, cfg: &Config) -> CheckResult {
+    let host = match &cfg.ipfs {
+        Ipfs::Bundled => return CheckResult::Skipped("ipfs is bundled with ceramic".to_string()),
+        Ipfs::Remote(r) => r.host.trim_end_matches('/'),
+    };
+    // the ipfs rpc api only accepts POST
+    match client.post(format!("{}/api/v0/id", host)).send().await {
+        Ok(r) if r.status().is_success() => CheckResult::Passed,
+        Ok(r) => CheckResult::Failed(format!("{} responded with {}", host, r.status())),
+        Err(e) => CheckResult::Failed(e.to_string()),
+    }
+}
+
+async fn check_db(cfg: &Config) -> CheckResult {
+    let url = match cfg.indexing.db.url() {
+        Ok(url) => url,
+        Err(e) => return CheckResult::Failed(e.to_string()),
+    };
+    let connect = async {
+        if url.starts_with("postgres") {
+            sqlx::postgres::PgConnection::connect(&url)
+                .await?
+                .close()
+                .await
+        } else {
+            sqlx::sqlite::SqliteConnection::connect(&url)
+                .await?
+                .close()
+                .await
+        }
+    };
+    match tokio::time::timeout(CHECK_TIMEOUT, connect).await {
+        Ok(Ok(())) => CheckResult::Passed,
+        Ok(Err(e)) => CheckResult::Failed(format!("{}, {}", cfg.indexing.db, e)),
+        Err(_) => CheckResult::Failed(format!("timed out connecting to {}", cfg.indexing.db)),
+    }
+}
+
+async fn report(
+    cfg: &Config,
+    client: &reqwest::Client,
+    started: Instant,
+    mut checks: Vec<(ReadinessStage, CheckResult)>,
+    output_file: &Path,
+) -> ReadinessReport {
+    let failed = checks
+        .iter()
+        .find(|(_, r)| matches!(r, CheckResult::Failed(_)))
+        .map(|(stage, _)| *stage)
+        .unwrap_or(ReadinessStage::ProcessAlive);
+    // a hung daemon is often waiting on ipfs or the database, so check them for the report
+    if failed != ReadinessStage::ProcessAlive {
+        for (stage, result) in checks.iter_mut() {
+            if matches!(result, CheckResult::NotRun) {
+                match stage {
+                    ReadinessStage::Ipfs => *result = check_ipfs(client, cfg).await,
+                    ReadinessStage::Database => *result = check_db(cfg).await,
+                    _ => {}
+                }
+            }
+        }
+    }
+    let output = tail(output_file, OUTPUT_LINES)
+        .await
+        .unwrap_or_else(|e| vec![format!("Failed to read {}: {}", output_file.display(), e)]);
+    ReadinessReport {
+        failed,
+        elapsed: started.elapsed(),
+        checks,
+        output_file: output_file.to_path_buf(),
+        output,
+    }
+}
+
+/// Wait for ceramic to become ready, running each stage in order until all pass. Fails with a
+/// [`ReadinessReport`] if the process exits or the timeout elapses first.
+pub async fn wait_until_ready(
+    cfg: &Config,
+    exited: &mut Receiver<ExitStatus>,
+    timeout: Duration,
+    output_file: &Path,
+) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let client = reqwest::Client::builder().timeout(CHECK_TIMEOUT).build()?;
+    let mut sp = Spinner::new(Spinners::Star2, "Waiting for ceramic to start".into());
+
+    let mut checks = vec![
+        (ReadinessStage::ProcessAlive, CheckResult::NotRun),
+        (ReadinessStage::PortBound, CheckResult::NotRun),
+        (ReadinessStage::Healthcheck, CheckResult::NotRun),
+        (ReadinessStage::Ipfs, CheckResult::NotRun),
+        (ReadinessStage::Database, CheckResult::NotRun),
+    ];
+    loop {
+        let mut ready = true;
+        for (stage, result) in checks.iter_mut() {
+            if *stage != ReadinessStage::ProcessAlive
+                && matches!(result, CheckResult::Passed | CheckResult::Skipped(_))
+            {
+                continue;
+            }
+            *result = match stage {
+                ReadinessStage::ProcessAlive => match exited.try_recv() {
+                    Ok(status) => CheckResult::Failed(format!("ceramic {}", status)),
+                    Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                        CheckResult::Failed("ceramic exited".to_string())
+                    }
+                    Err(tokio::sync::mpsc::error::TryRecvError::Empty) => CheckResult::Passed,
+                },
+                ReadinessStage::PortBound => check_port(cfg).await,
+                ReadinessStage::Healthcheck => check_healthcheck(&client, cfg).await,
+                ReadinessStage::Ipfs => check_ipfs(&client, cfg).await,
+                ReadinessStage::Database => check_db(cfg).await,
+            };
+            log::debug!("Readiness check {}: {}", stage, result);
+            if *stage == ReadinessStage::ProcessAlive {
+                if let CheckResult::Failed(_) = result {
+                    sp.stop_with_newline();
+                    return Err(report(cfg, &client, started, checks, output_file)
+                        .await
+                        .into());
+                }
+                // the process can still exit, so check it on every pass
+                continue;
+            }
+            if let CheckResult::Failed(_) = result {
+                ready = false;
+                break;
+            }
+        }
+        if ready {
+            break;
+        }
+        if Instant::now() >= deadline {
+            sp.stop_with_newline();
+            return Err(report(cfg, &client, started, checks, output_file)
+                .await
+                .into());
+        }
+        tokio::select! {
+            status = exited.recv() => {
+                let detail = status
+                    .map(|s| format!("ceramic {}", s))
+                    .unwrap_or_else(|| "ceramic exited".to_string());
+                checks[0].1 = CheckResult::Failed(detail);
+                sp.stop_with_newline();
+                return Err(report(cfg, &client, started, checks, output_file)
+                    .await
+                    .into());
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+    sp.stop_with_newline();
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use crate::daemon_log::{DaemonLog, DaemonOutput, Stream};
+use crate::lifecycle::{remove_daemon_pid, signal, write_daemon_pid};
+use serde::{Deserialize, Serialize};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::signal::unix::SignalKind;
+
+/// File the restart history is appended to, one json record per line
+pub const HISTORY_FILE: &str = "ceramic-restarts.jsonl";
+
+pub(crate) const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the supervisor restarts a crashed daemon
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SupervisorOptions {
+    /// Crashes allowed within `window` before giving up
+    pub max_restarts: u32,
+    pub window: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Shell command run before each restart
+    pub on_restart: Option<String>,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(600),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            on_restart: None,
+        }
+    }
+}
+
+impl SupervisorOptions {
+    /// Run the daemon without restarting it, only capturing its output
+    pub fn no_restarts() -> Self {
+        Self {
+            max_restarts: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Backoff before the given consecutive restart, doubling from the initial backoff
+    fn backoff(&self, consecutive: u32) -> Duration {
+        let factor = 2u32.saturating_pow(consecutive.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Arguments for the `supervise` command to run with these options
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--max-restarts".to_string(),
+            self.max_restarts.to_string(),
+            "--restart-window".to_string(),
+            self.window.as_secs().to_string(),
+            "--initial-backoff".to_string(),
+            self.initial_backoff.as_secs().to_string(),
+            "--max-backoff".to_string(),
+            self.max_backoff.as_secs().to_string(),
+        ];
+        if let Some(hook) = &self.on_restart {
+            args.push("--on-restart".to_string());
+            args.push(hook.clone());
+        }
+        args
+    }
+}
+
+/// Crashes within the restart window, and how many happened in a row
+#[derive(Default)]
+struct CrashHistory {
+    crashes: Vec<Instant>,
+    consecutive: u32,
+}
+
+impl CrashHistory {
+    /// Record a crash at `now` of a daemon that had been up for `uptime`
+    fn crashed(&mut self, now: Instant, uptime: Duration, window: Duration) {
+        self.crashes.retain(|t| now.duration_since(*t) < window);
+        self.crashes.push(now);
+        // a daemon that stayed up for a full window is no longer crash looping
+        self.consecutive = if uptime >= window {
+            1
+        } else {
+            self.consecutive + 1
+        };
+    }
+
+    fn count(&self) -> u32 {
+        self.crashes.len() as u32
+    }
+}
+
+#[derive(Serialize)]
+struct RestartRecord {
+    /// Seconds since the unix epoch
+    time: u64,
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    uptime_secs: u64,
+    restart: u32,
+    backoff_secs: u64,
+    reason: String,
+}
+
+fn exit_reason(status: &ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        _ => "exited".to_string(),
+    }
+}
+
+async fn record(working_directory: &Path, record: &RestartRecord) -> anyhow::Result<()> {
+    let mut f = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(working_directory.join(HISTORY_FILE))
+        .await?;
+    f.write_all(format!("{}\n", serde_json::to_string(record)?).as_bytes())
+        .await?;
+    f.flush().await?;
+    Ok(())
+}
+
+fn spawn(
+    working_directory: &Path,
+    daemon_config_file: &Path,
+    daemon_log: &DaemonLog,
+) -> anyhow::Result<Child> {
+    let mut child = Command::new("sh")
+        .args([
+            "ceramic",
+            "daemon",
+            "--config",
+            &daemon_config_file.display().to_string(),
+        ])
+        .current_dir(working_directory)
+        .process_group(0)
+        .kill_on_drop(false)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    daemon_log.capture(&mut child);
+    Ok(child)
+}
+
+async fn run_hook(
+    working_directory: &Path,
+    hook: &str,
+    restart: u32,
+    status: &ExitStatus,
+) -> anyhow::Result<()> {
+    let hook_status = Command::new("sh")
+        .args(["-c", hook])
+        .current_dir(working_directory)
+        .env("WHEEL_RESTART_COUNT", restart.to_string())
+        .env("WHEEL_EXIT_REASON", exit_reason(status))
+        .env(
+            "WHEEL_EXIT_CODE",
+            status.code().map(|c| c.to_string()).unwrap_or_default(),
+        )
+        .status()
+        .await?;
+    if !hook_status.success() {
+        log::warn!("Restart hook {} failed with {}", hook, hook_status);
+    }
+    Ok(())
+}
+
+/// Stop the daemon's process group, which does not receive signals sent to the supervisor
+async fn shutdown(child: &mut Child) -> anyhow::Result<()> {
+    if let Some(pid) = child.id() {
+        signal(pid, "TERM").await?;
+    }
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, child.wait())
+        .await
+        .is_err()
+    {
+        log::warn!("Ceramic did not exit after shutdown, killing it");
+        if let Some(pid) = child.id() {
+            signal(pid, "KILL").await?;
+        }
+        child.wait().await?;
+    }
+    Ok(())
+}
+
+/// Run the daemon in the foreground, restarting it with exponential backoff when it crashes.
+/// Returns when the daemon exits cleanly, on ctrl-c or SIGTERM, or after too many crashes within
+/// the restart window. The daemon's output is written to the daemon log.
+pub async fn supervise(
+    working_directory: PathBuf,
+    daemon_config_file: PathBuf,
+    opts: SupervisorOptions,
+    output: DaemonOutput,
+) -> anyhow::Result<()> {
+    let daemon_log = DaemonLog::open(&working_directory, output).await?;
+    let mut terminate = tokio::signal::unix::signal(SignalKind::terminate())?;
+    let mut history = CrashHistory::default();
+    let mut restarts = 0;
+    loop {
+        let started = Instant::now();
+        let mut child = spawn(&working_directory, &daemon_config_file, &daemon_log)?;
+        let pid = child.id().unwrap_or_default();
+        write_daemon_pid(&working_directory, pid, std::process::id()).await?;
+        let msg = format!("Supervising ceramic with pid {}", pid);
+        log::info!("{}", msg);
+        daemon_log.write(Stream::Wheel, &msg).await;
+        let msg = tokio::select! {
+            status = child.wait() => Err(status?),
+            _ = tokio::signal::ctrl_c() => Ok("Received ctrl-c, stopping ceramic"),
+            _ = terminate.recv() => Ok("Received SIGTERM, stopping ceramic"),
+        };
+        let status = match msg {
+            Ok(msg) => {
+                log::info!("{}", msg);
+                daemon_log.write(Stream::Wheel, msg).await;
+                let res = shutdown(&mut child).await;
+                remove_daemon_pid(&working_directory).await;
+                return res;
+            }
+            Err(status) => status,
+        };
+        remove_daemon_pid(&working_directory).await;
+        if status.success() {
+            let msg = "Ceramic exited cleanly, not restarting";
+            log::info!("{}", msg);
+            daemon_log.write(Stream::Wheel, msg).await;
+            return Ok(());
+        }
+        let uptime = started.elapsed();
+        history.crashed(Instant::now(), uptime, opts.window);
+        let reason = exit_reason(&status);
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        if history.count() > opts.max_restarts {
+            record(
+                &working_directory,
+                &RestartRecord {
+                    time,
+                    exit_code: status.code(),
+                    signal: status.signal(),
+                    uptime_secs: uptime.as_secs(),
+                    restart: restarts,
+                    backoff_secs: 0,
+                    reason: format!("{}, giving up", reason),
+                },
+            )
+            .await?;
+            let msg = if opts.max_restarts == 0 {
+                format!("Ceramic {}, not restarting", reason)
+            } else {
+                format!(
+                    "Ceramic {} and crashed {} times in {}s, giving up",
+                    reason,
+                    history.count(),
+                    opts.window.as_secs()
+                )
+            };
+            daemon_log.write(Stream::Wheel, &msg).await;
+            anyhow::bail!(
+                "{}. See {} and {}",
+                msg,
+                daemon_log.path().display(),
+                working_directory.join(HISTORY_FILE).display()
+            );
+        }
+
+        restarts += 1;
+        let backoff = opts.backoff(history.consecutive);
+        let msg = format!(
+            "Ceramic {}, restarting in {}s (restart {})",
+            reason,
+            backoff.as_secs(),
+            restarts
+        );
+        log::warn!("{}", msg);
+        daemon_log.write(Stream::Wheel, &msg).await;
+        record(
+            &working_directory,
+            &RestartRecord {
+                time,
+                exit_code: status.code(),
+                signal: status.signal(),
+                uptime_secs: uptime.as_secs(),
+                restart: restarts,
+                backoff_secs: backoff.as_secs(),
+                reason,
+            },
+        )
+        .await?;
+        if let Some(hook) = &opts.on_restart {
+            run_hook(&working_directory, hook, restarts, &status).await?;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = terminate.recv() => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_double_backoff_up_to_max() {
+        let opts = SupervisorOptions::default();
+        let backoffs: Vec<_> = (1..=8).map(|n| opts.backoff(n).as_secs()).collect();
+        assert_eq!(backoffs, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(opts.backoff(0), opts.initial_backoff);
+        assert_eq!(opts.backoff(u32::MAX), opts.max_backoff);
+
+        let unbounded = SupervisorOptions {
+            max_backoff: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(unbounded.backoff(40), Duration::from_secs(u32::MAX as u64));
+    }
+
+    #[test]
+    fn should_reset_consecutive_crashes_after_a_full_window() {
+        let window = Duration::from_secs(600);
+        let start = Instant::now();
+        let mut history = CrashHistory::default();
+        history.crashed(start, Duration::from_secs(1), window);
+        history.crashed(
+            start + Duration::from_secs(5),
+            Duration::from_secs(2),
+            window,
+        );
+        assert_eq!(history.consecutive, 2);
+        assert_eq!(history.count(), 2);
+
+        history.crashed(start + Duration::from_secs(700), window, window);
+        assert_eq!(history.consecutive, 1);
+        assert_eq!(history.count(), 1);
+    }
+}